@@ -0,0 +1,388 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::compose::load_composed;
+use crate::dotenv;
+use crate::models::{Config, ConfigItem, ItemType};
+use crate::resolve::env_var_names;
+use crate::rp_macros::DEFAULT_REPO_DIR;
+
+mod python;
+mod rust;
+mod typescript;
+
+/// The languages code can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Rust,
+    TypeScript,
+    Python,
+}
+
+impl FromStr for Lang {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "rust" | "rs" => Ok(Lang::Rust),
+            "typescript" | "ts" => Ok(Lang::TypeScript),
+            "python" | "py" => Ok(Lang::Python),
+            _ => anyhow::bail!("'{}' is not a supported language; use rust, typescript or python", s),
+        }
+    }
+}
+
+/// Options for generating code.
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// The name of the generated type; defaults to the `config_name` default in PascalCase.
+    pub type_name: Option<String>,
+}
+
+/// A setting as the generated code sees it.
+#[derive(Debug, Clone)]
+pub struct Field {
+    /// The key in the saved output.
+    pub key: String,
+    /// The key in snake_case, safe to use as a field name in every supported language.
+    pub name: String,
+    pub description: String,
+    pub item_type: ItemType,
+    pub secret: bool,
+    /// The environment variables the value is read from, in the order they are checked: those
+    /// rpcfg honors (see `resolve::env_var_names`), then the uppercased key the `.env` output uses.
+    pub env_vars: Vec<String>,
+}
+
+/// Everything the generators need to know about a schema.
+#[derive(Debug, Clone)]
+pub struct Spec {
+    /// The schema file the code is generated from, for the header comment.
+    pub source: String,
+    pub type_name: String,
+    pub project: String,
+    pub config_name: String,
+    /// The directory the outputs are read from for `stored: "repo"`, relative to the schema file.
+    pub repo_dir: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+impl Spec {
+    /// Builds the spec from a schema, using the defaults of its rpcfg items.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if two keys map to the same field name, a name is
+    /// not valid, or `repo_dir` leaves the directory of the schema file.
+    pub fn new(config: &Config, source: &str, options: &CodegenOptions) -> Result<Spec> {
+        let rpcfg_default = |key: &str| {
+            config
+                .get_settings(key)
+                .first()
+                .map(|item| item.default.clone())
+                .unwrap_or_default()
+        };
+        let config_name = rpcfg_default("config_name");
+        let type_name = match &options.type_name {
+            Some(name) => name.clone(),
+            None => pascal_case(&config_name),
+        };
+        if !is_identifier(&type_name) {
+            anyhow::bail!("'{}' is not a valid type name; pass one with --name", type_name);
+        }
+        let repo_dir = match rpcfg_default("stored").as_str() {
+            "repo" => {
+                let dir = Some(rpcfg_default("repo_dir"))
+                    .filter(|dir| !dir.trim().is_empty())
+                    .unwrap_or_else(|| DEFAULT_REPO_DIR.to_string());
+                let item = ConfigItem {
+                    key: "repo_dir".to_string(),
+                    ..Default::default()
+                };
+                item.validate_value(&dir)
+                    .map_err(|e| anyhow::anyhow!("Invalid repo_dir '{}': {}", dir, e))?;
+                Some(dir)
+            }
+            _ => None,
+        };
+
+        let mut fields: Vec<Field> = Vec::new();
+        for item in &config.app {
+            let field = Field::new(item);
+            if let Some(other) = fields.iter().find(|other| other.name == field.name) {
+                anyhow::bail!(
+                    "The keys '{}' and '{}' both become the field '{}'; rename one of them",
+                    other.key,
+                    field.key,
+                    field.name
+                );
+            }
+            fields.push(field);
+        }
+
+        Ok(Spec {
+            source: source.to_string(),
+            type_name,
+            project: rpcfg_default("project_name"),
+            config_name,
+            repo_dir,
+            fields,
+        })
+    }
+}
+
+impl Field {
+    fn new(item: &ConfigItem) -> Field {
+        let mut env_vars = env_var_names(item);
+        let env_name = item.key.to_uppercase();
+        if dotenv::is_valid_name(&env_name) && !env_vars.contains(&env_name) {
+            env_vars.push(env_name);
+        }
+        Field {
+            key: item.key.clone(),
+            name: field_name(&item.key),
+            description: item.description.clone(),
+            item_type: item.item_type,
+            secret: item.secret,
+            env_vars,
+        }
+    }
+}
+
+/// Generates code for a schema.
+///
+/// The output depends only on the schema and the options, so it can be committed and checked
+/// with `rpcfg codegen --check`.
+///
+/// # Arguments
+///
+/// * `config` - The schema, with `extends` and `include` merged in.
+/// * `source` - The path of the schema file, named in the header comment.
+/// * `lang` - The language to generate.
+/// * `options` - The name of the generated type.
+///
+/// # Errors
+///
+/// This function will return an error if the schema cannot be turned into valid names (see `Spec::new`).
+pub fn generate(config: &Config, source: &str, lang: Lang, options: &CodegenOptions) -> Result<String> {
+    let spec = Spec::new(config, source, options)?;
+    Ok(match lang {
+        Lang::Rust => rust::generate(&spec),
+        Lang::TypeScript => typescript::generate(&spec),
+        Lang::Python => python::generate(&spec),
+    })
+}
+
+/// Loads a schema file and generates code for it (see `generate`).
+///
+/// # Errors
+///
+/// This function will return an error if the schema cannot be loaded or turned into valid names.
+pub fn generate_file(schema_path: &str, lang: Lang, options: &CodegenOptions) -> Result<String> {
+    let config = load_composed(schema_path)?;
+    generate(&config, &source_name(schema_path), lang, options)
+}
+
+/// The file name of the schema, so the header does not depend on where the code was generated.
+fn source_name(schema_path: &str) -> String {
+    Path::new(schema_path)
+        .file_name()
+        .map_or_else(|| schema_path.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// Generates Rust code for a schema from a build script.
+///
+/// The code is written to `out_file` under `OUT_DIR`, and Cargo is told to run the build
+/// script again when the schema or a file it builds on changes. Code using a setting that was
+/// removed from the schema, or using it with its old type, then fails to compile.
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     rpcfg::codegen::build("repo_config.json", "config.rs").unwrap();
+/// }
+///
+/// // src/config.rs
+/// include!(concat!(env!("OUT_DIR"), "/config.rs"));
+/// ```
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The path of the generated file.
+///
+/// # Errors
+///
+/// This function will return an error if `OUT_DIR` is not set, the schema cannot be loaded, or
+/// the file cannot be written.
+pub fn build(schema_path: &str, out_file: &str) -> Result<PathBuf> {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| anyhow::anyhow!("OUT_DIR is not set; call codegen::build from a build script"))?;
+    let config = load_composed(schema_path)?;
+
+    let mut sources = BTreeSet::from([schema_path.to_string()]);
+    sources.extend(
+        config
+            .rpcfg
+            .iter()
+            .chain(config.app.iter())
+            .map(|item| item.defined_in.clone())
+            .filter(|file| !file.is_empty()),
+    );
+    for source in &sources {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+
+    let code = generate(&config, &source_name(schema_path), Lang::Rust, &CodegenOptions::default())?;
+    let path = Path::new(&out_dir).join(out_file);
+    fs::write(&path, code).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Converts a key to snake_case, replacing characters that cannot be in a name with `_`.
+///
+/// Names that are keywords in a supported language get a trailing `_`, and names starting
+/// with a digit a leading one.
+pub fn field_name(key: &str) -> String {
+    let mut name = String::new();
+    let mut previous: Option<char> = None;
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit()) {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+        previous = Some(c);
+    }
+    let name = name.trim_end_matches('_').to_string();
+    if name.is_empty() {
+        "field_".to_string()
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+/// Converts a field name to camelCase, keeping leading and trailing underscores.
+pub fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper_next = false;
+    for (i, c) in name.char_indices() {
+        if c == '_' && i > 0 && name[i + 1..].starts_with(|next: char| next.is_ascii_alphanumeric()) {
+            upper_next = true;
+        } else if upper_next {
+            camel.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// Converts a name such as `rpcfg_config` or `web-api` to PascalCase.
+pub fn pascal_case(name: &str) -> String {
+    field_name(name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quotes a string as a literal, escaping it the same way in every supported language.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Names that cannot be used as field names in a supported language.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+    "try", "typeof", "unsized", "virtual", "yield", "and", "assert", "class", "def", "del", "elif",
+    "except", "finally", "from", "global", "import", "is", "lambda", "nonlocal", "not", "or",
+    "pass", "raise", "with",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_test_input_file, safe_test};
+
+    safe_test!(test_codegen_names, {
+        assert_eq!(field_name("azureLocation"), "azure_location");
+        assert_eq!(field_name("API_URL"), "api_url");
+        assert_eq!(field_name("db.host-name"), "db_host_name");
+        assert_eq!(field_name("type"), "type_");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(pascal_case("rpcfg_config"), "RpcfgConfig");
+        assert_eq!(pascal_case("web-api"), "WebApi");
+        assert_eq!(camel_case("db_host_name"), "dbHostName");
+        assert_eq!(camel_case("type_"), "type_");
+        assert_eq!(camel_case("_2fa"), "_2fa");
+
+        let (mut config, _temp_dir) = create_test_input_file!("codegen");
+        config.app[0].key = "dbHost".to_string();
+        config.app[1].key = "db_host".to_string();
+        let err = generate(&config, "repo_config.json", Lang::Rust, &CodegenOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("both become the field"));
+
+        // Repo outputs are found next to the schema file, which must contain them
+        let (mut config, _temp_dir) = create_test_input_file!("codegen-repo");
+        config.rpcfg[0].default = "repo".to_string();
+        config.rpcfg.push(ConfigItem {
+            key: "repo_dir".to_string(),
+            default: "config/out".to_string(),
+            ..Default::default()
+        });
+        let code = generate(&config, "repo_config.json", Lang::Rust, &CodegenOptions::default())?;
+        assert!(code.contains("pub const REPO_DIR: &str = \"config/out\";"));
+        assert!(code.contains("pub fn load(schema_dir: impl AsRef<std::path::Path>, environment: &str)"));
+        config.rpcfg.last_mut().unwrap().default = "../out".to_string();
+        let err = generate(&config, "repo_config.json", Lang::Rust, &CodegenOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("Invalid repo_dir"));
+
+        // A schema without app items reads nothing from the environment
+        config.app.clear();
+        config.rpcfg.pop();
+        let code = generate(&config, "repo_config.json", Lang::TypeScript, &CodegenOptions::default())?;
+        assert!(code.contains("(_env: Record<string, string | undefined> = process.env)"));
+        assert!(!code.contains("function readEnv"));
+
+        Ok(())
+    });
+}
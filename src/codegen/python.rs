@@ -0,0 +1,256 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::{quote, Field, Spec};
+use crate::models::ItemType;
+
+fn python_type(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "str",
+        ItemType::Url => "Optional[str]",
+        ItemType::Integer => "Optional[int]",
+        ItemType::Number => "Optional[float]",
+        ItemType::Boolean => "Optional[bool]",
+    }
+}
+
+fn parser(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "_parse_string",
+        ItemType::Integer => "_parse_integer",
+        ItemType::Number => "_parse_number",
+        ItemType::Boolean => "_parse_boolean",
+        ItemType::Url => "_parse_url",
+    }
+}
+
+/// Writes a docstring, on one line when the text has one line.
+fn docstring(out: &mut String, indent: &str, lines: &[String]) {
+    let mut lines: Vec<String> = lines
+        .iter()
+        .map(|line| line.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\""))
+        .collect();
+    // A quote right before the closing quotes would end the docstring early
+    if let Some(last) = lines.last_mut().filter(|last| last.ends_with('"')) {
+        last.pop();
+        last.push_str("\\\"");
+    }
+    match lines.as_slice() {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "{}\"\"\"{}\"\"\"", indent, line);
+        }
+        [first, rest @ ..] => {
+            let _ = writeln!(out, "{}\"\"\"{}", indent, first);
+            for line in rest {
+                if line.is_empty() {
+                    out.push('\n');
+                } else {
+                    let _ = writeln!(out, "{}{}", indent, line);
+                }
+            }
+            let _ = writeln!(out, "{}\"\"\"", indent);
+        }
+    }
+}
+
+fn field_doc(field: &Field) -> Vec<String> {
+    let mut lines: Vec<String> = field
+        .description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if field.secret {
+        lines.push(String::new());
+        lines.push("Secret: do not log this value.".to_string());
+    }
+    lines
+}
+
+/// Generates a frozen dataclass with one typed attribute per app item, and a loader that reads
+/// the environment.
+///
+/// Only the value checks the schema uses are emitted.
+pub(super) fn generate(spec: &Spec) -> String {
+    let name = &spec.type_name;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# Generated by rpcfg from {}. Do not edit; run `rpcfg codegen --lang python` to update.",
+        spec.source
+    );
+    let used: BTreeSet<&str> = spec.fields.iter().map(|field| parser(field.item_type)).collect();
+    out.push_str("\nfrom __future__ import annotations\n\n");
+    if used.contains("_parse_number") {
+        out.push_str("import math\n");
+    }
+    out.push_str("import os\n");
+    if used.contains("_parse_integer") || used.contains("_parse_url") {
+        out.push_str("import re\n");
+    }
+    out.push_str(
+        "from dataclasses import dataclass
+from typing import Mapping, Optional, Sequence
+
+
+",
+    );
+    // Secret values must not show up in repr() or str(), so the repr is written by hand
+    let has_secrets = spec.fields.iter().any(|field| field.secret);
+    if has_secrets {
+        out.push_str("@dataclass(frozen=True, repr=False)\n");
+    } else {
+        out.push_str("@dataclass(frozen=True)\n");
+    }
+    let _ = writeln!(out, "class {}:", name);
+    docstring(
+        &mut out,
+        "    ",
+        &[format!("The settings of the {}/{} configuration.", spec.project, spec.config_name)],
+    );
+    for field in &spec.fields {
+        out.push('\n');
+        let _ = writeln!(out, "    {}: {}", field.name, python_type(field.item_type));
+        docstring(&mut out, "    ", &field_doc(field));
+    }
+
+    out.push_str(
+        "
+    @classmethod
+    def from_env(cls, env: Optional[Mapping[str, str]] = None) -> ",
+    );
+    let _ = writeln!(out, "{}:", name);
+    docstring(
+        &mut out,
+        "        ",
+        &[
+            "Reads the settings from the environment: each item's `temp_environment_variable_name`,".to_string(),
+            "then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.".to_string(),
+            String::new(),
+            "Raises ValueError naming the setting when a value does not fit its type.".to_string(),
+        ],
+    );
+    out.push_str("        env = os.environ if env is None else env\n        return cls(\n");
+    for field in &spec.fields {
+        let vars: Vec<String> = field.env_vars.iter().map(|var| quote(var)).collect();
+        let vars = match vars.as_slice() {
+            [var] => format!("({},)", var),
+            _ => format!("({})", vars.join(", ")),
+        };
+        let _ = writeln!(
+            out,
+            "            {}={}({}, _read_env(env, {})),",
+            field.name,
+            parser(field.item_type),
+            quote(&field.key),
+            vars
+        );
+    }
+    out.push_str("        )\n");
+
+    if has_secrets {
+        write_repr(&mut out, spec);
+    }
+
+    out.push_str(READ_ENV);
+    for (parser, code) in HELPERS {
+        if used.contains(parser) {
+            out.push_str(code);
+        }
+    }
+    out
+}
+
+/// Writes a `__repr__` that shows `***` in place of secret values.
+fn write_repr(out: &mut String, spec: &Spec) {
+    out.push_str("\n    def __repr__(self) -> str:\n");
+    docstring(out, "        ", &["Shows the settings with *** in place of secret values.".to_string()]);
+    out.push_str("        return (\n");
+    let _ = writeln!(out, "            \"{}(\"", spec.type_name);
+    for (i, field) in spec.fields.iter().enumerate() {
+        let end = if i + 1 == spec.fields.len() { ")" } else { ", " };
+        if field.secret {
+            let _ = writeln!(out, "            \"{}='***'{}\"", field.name, end);
+        } else {
+            let _ = writeln!(out, "            f\"{}={{self.{}!r}}{}\"", field.name, field.name, end);
+        }
+    }
+    out.push_str("        )\n");
+}
+
+const READ_ENV: &str = "
+
+def _read_env(env: Mapping[str, str], names: Sequence[str]) -> str:
+    for name in names:
+        value = env.get(name)
+        if value is not None:
+            return value
+    return \"\"
+";
+
+/// The value checks, matching `ItemType::check`, in the order they are emitted.
+const HELPERS: &[(&str, &str)] = &[
+    (
+        "_parse_string",
+        "
+
+def _parse_string(_key: str, value: str) -> str:
+    return value
+",
+    ),
+    (
+        "_parse_integer",
+        "
+
+def _parse_integer(key: str, value: str) -> Optional[int]:
+    if value == \"\":
+        return None
+    if not re.fullmatch(r\"[+-]?[0-9]+\", value):
+        raise ValueError(f\"{key}: '{value}' is not a valid integer\")
+    return int(value)
+",
+    ),
+    (
+        "_parse_number",
+        "
+
+def _parse_number(key: str, value: str) -> Optional[float]:
+    if value == \"\":
+        return None
+    try:
+        number = float(value)
+        if value.strip() != value or \"_\" in value or not math.isfinite(number):
+            raise ValueError
+        return number
+    except ValueError:
+        raise ValueError(f\"{key}: '{value}' is not a valid number\") from None
+",
+    ),
+    (
+        "_parse_boolean",
+        "
+
+def _parse_boolean(key: str, value: str) -> Optional[bool]:
+    if value == \"\":
+        return None
+    if value not in (\"true\", \"false\"):
+        raise ValueError(f\"{key}: '{value}' is not a valid boolean\")
+    return value == \"true\"
+",
+    ),
+    (
+        "_parse_url",
+        "
+
+def _parse_url(key: str, value: str) -> Optional[str]:
+    if value == \"\":
+        return None
+    url = value.strip()
+    if \"://\" not in url or re.search(r\"\\s\", url):
+        raise ValueError(f\"{key}: '{value}' is not a valid url\")
+    return url
+",
+    ),
+];
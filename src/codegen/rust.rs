@@ -0,0 +1,258 @@
+use std::fmt::Write;
+
+use super::{quote, Field, Spec};
+use crate::models::ItemType;
+
+/// The type of a field. Only text can be empty, so the other types are `None` when unset.
+fn rust_type(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "String",
+        ItemType::Url => "Option<String>",
+        ItemType::Integer => "Option<i64>",
+        ItemType::Number => "Option<f64>",
+        ItemType::Boolean => "Option<bool>",
+    }
+}
+
+fn parser(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "parse_string",
+        ItemType::Integer => "parse_integer",
+        ItemType::Number => "parse_number",
+        ItemType::Boolean => "parse_boolean",
+        ItemType::Url => "parse_url",
+    }
+}
+
+fn doc_lines(out: &mut String, indent: &str, text: &str) {
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let _ = writeln!(out, "{}/// {}", indent, line.trim());
+    }
+}
+
+/// Generates a struct with one typed field per app item, and loaders for the saved output
+/// and the environment.
+///
+/// The code only depends on the standard library and `serde_json`.
+pub(super) fn generate(spec: &Spec) -> String {
+    let name = &spec.type_name;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Generated by rpcfg from {}. Do not edit; run `rpcfg codegen --lang rust` to update.",
+        spec.source
+    );
+    out.push('\n');
+
+    // Secret values must not show up in `{:?}`, so their Debug is written by hand
+    let has_secrets = spec.fields.iter().any(|field| field.secret);
+    let _ = writeln!(out, "/// The settings of the {}/{} configuration.", spec.project, spec.config_name);
+    if has_secrets {
+        out.push_str("#[derive(Clone, PartialEq)]\n#[allow(dead_code)]\n");
+    } else {
+        out.push_str("#[derive(Debug, Clone, PartialEq)]\n#[allow(dead_code)]\n");
+    }
+    let _ = writeln!(out, "pub struct {} {{", name);
+    for field in &spec.fields {
+        doc_lines(&mut out, "    ", &field.description);
+        if field.secret {
+            out.push_str("    ///\n    /// Secret: do not log this value.\n");
+        }
+        let _ = writeln!(out, "    pub {}: {},", field.name, rust_type(field.item_type));
+    }
+    out.push_str("}\n\n");
+
+    if has_secrets {
+        write_debug(&mut out, spec);
+    }
+
+    out.push_str("#[allow(dead_code)]\n");
+    let _ = writeln!(out, "impl {} {{", name);
+    let _ = writeln!(out, "    /// The project the settings are saved under.");
+    let _ = writeln!(out, "    pub const PROJECT: &str = {};", quote(&spec.project));
+    let _ = writeln!(out, "    /// The config name the settings are saved under.");
+    let _ = writeln!(out, "    pub const CONFIG_NAME: &str = {};", quote(&spec.config_name));
+    let _ = writeln!(out, "    /// The keys of the settings, in schema order.");
+    let keys: Vec<String> = spec.fields.iter().map(|field| quote(&field.key)).collect();
+    let _ = writeln!(out, "    pub const KEYS: &[&str] = &[{}];", keys.join(", "));
+    out.push('\n');
+
+    write_from_map(&mut out, &spec.fields);
+    write_from_env(&mut out, &spec.fields);
+    write_loaders(&mut out, spec);
+    out.push_str(HELPERS);
+    out.push_str("}\n");
+    out
+}
+
+/// Writes a Debug implementation that prints `***` in place of secret values.
+fn write_debug(out: &mut String, spec: &Spec) {
+    let _ = writeln!(out, "impl std::fmt::Debug for {} {{", spec.type_name);
+    out.push_str("    /// Formats the settings with `***` in place of secret values.\n");
+    out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n");
+    let _ = writeln!(out, "        f.debug_struct({})", quote(&spec.type_name));
+    for field in &spec.fields {
+        if field.secret {
+            let _ = writeln!(out, "            .field({}, &\"***\")", quote(&field.name));
+        } else {
+            let _ = writeln!(out, "            .field({}, &self.{})", quote(&field.name), field.name);
+        }
+    }
+    out.push_str("            .finish()\n    }\n}\n\n");
+}
+
+fn write_from_map(out: &mut String, fields: &[Field]) {
+    out.push_str(
+        "    /// Builds the settings from keys and values, such as a saved output, checking each value's type.
+    ///
+    /// A missing key counts as an empty value, which leaves a setting unset.
+    pub fn from_map(values: &std::collections::HashMap<String, String>) -> Result<Self, String> {
+        let get = |key: &str| values.get(key).map(String::as_str).unwrap_or_default();
+        Ok(Self {
+",
+    );
+    for field in fields {
+        let key = quote(&field.key);
+        let _ = writeln!(
+            out,
+            "            {}: Self::{}({}, get({}))?,",
+            field.name,
+            parser(field.item_type),
+            key,
+            key
+        );
+    }
+    out.push_str("        })\n    }\n\n");
+}
+
+fn write_from_env(out: &mut String, fields: &[Field]) {
+    out.push_str(
+        "    /// Reads the settings from the environment: the item's `temp_environment_variable_name`,
+    /// then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.
+    pub fn from_env() -> Result<Self, String> {
+        let names: &[(&str, &[&str])] = &[
+",
+    );
+    for field in fields {
+        let vars: Vec<String> = field.env_vars.iter().map(|var| quote(var)).collect();
+        let _ = writeln!(out, "            ({}, &[{}]),", quote(&field.key), vars.join(", "));
+    }
+    out.push_str(
+        "        ];
+        let values = names
+            .iter()
+            .filter_map(|(key, vars)| {
+                vars.iter()
+                    .find_map(|var| std::env::var(var).ok())
+                    .map(|value| (key.to_string(), value))
+            })
+            .collect();
+        Self::from_map(&values)
+    }
+
+",
+    );
+}
+
+fn write_loaders(out: &mut String, spec: &Spec) {
+    match &spec.repo_dir {
+        Some(repo_dir) => {
+            let _ = writeln!(
+                out,
+                "    /// The directory, relative to the schema file, that `stored: \"repo\"` saves the outputs in.
+    pub const REPO_DIR: &str = {};
+
+    /// Reads the settings `rpcfg collect` saved for an environment next to the schema file in
+    /// `schema_dir`, the way rpcfg resolves `repo_dir`.
+    pub fn load(schema_dir: impl AsRef<std::path::Path>, environment: &str) -> Result<Self, String> {{
+        let file = format!(\"{{}}-{{}}.json\", Self::CONFIG_NAME, environment);
+        Self::load_file(&schema_dir.as_ref().join(Self::REPO_DIR).join(file))",
+                quote(repo_dir)
+            );
+        }
+        None => out.push_str(
+            "    /// Reads the settings `rpcfg collect` saved for an environment.
+    pub fn load(environment: &str) -> Result<Self, String> {
+        let file = format!(\"{}-{}.json\", Self::CONFIG_NAME, environment);
+        Self::load_file(&Self::storage_root()?.join(Self::PROJECT).join(file))
+",
+        ),
+    }
+    out.push_str(
+        "    }
+
+    /// Reads the settings from a saved output file.
+    pub fn load_file(path: &std::path::Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!(\"Failed to read {}: {}\", path.display(), e))?;
+        let values = serde_json::from_str(&content)
+            .map_err(|e| format!(\"Failed to parse {}: {}\", path.display(), e))?;
+        Self::from_map(&values)
+    }
+
+",
+    );
+    if spec.repo_dir.is_none() {
+        out.push_str(STORAGE_ROOT);
+    }
+}
+
+/// Finds the storage root the way rpcfg does, without the `--root` option.
+const STORAGE_ROOT: &str = "    /// The rpcfg storage root: `RPCFG_HOME`, `~/.rpcfg` if it exists, then the XDG data directory.
+    fn storage_root() -> Result<std::path::PathBuf, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        if let Some(root) = var(\"RPCFG_HOME\") {
+            return Ok(root.into());
+        }
+        let home = var(\"HOME\").map(std::path::PathBuf::from);
+        if let Some(legacy) = home.as_ref().map(|home| home.join(\".rpcfg\")).filter(|dir| dir.is_dir()) {
+            return Ok(legacy);
+        }
+        if let Some(data_home) = var(\"XDG_DATA_HOME\") {
+            return Ok(std::path::PathBuf::from(data_home).join(\"rpcfg\"));
+        }
+        home.map(|home| home.join(\".local\").join(\"share\").join(\"rpcfg\"))
+            .ok_or_else(|| \"Cannot find the rpcfg storage root: set RPCFG_HOME or HOME\".to_string())
+    }
+
+";
+
+/// The value checks, matching `ItemType::check`.
+const HELPERS: &str = "    fn parse_string(_key: &str, value: &str) -> Result<String, String> {
+        Ok(value.to_string())
+    }
+
+    fn parse_integer(key: &str, value: &str) -> Result<Option<i64>, String> {
+        Self::parse_with(key, value, \"integer\", |value| value.parse().ok())
+    }
+
+    fn parse_number(key: &str, value: &str) -> Result<Option<f64>, String> {
+        Self::parse_with(key, value, \"number\", |value| {
+            value.parse().ok().filter(|number: &f64| number.is_finite())
+        })
+    }
+
+    fn parse_boolean(key: &str, value: &str) -> Result<Option<bool>, String> {
+        Self::parse_with(key, value, \"boolean\", |value| match value {
+            \"true\" => Some(true),
+            \"false\" => Some(false),
+            _ => None,
+        })
+    }
+
+    fn parse_url(key: &str, value: &str) -> Result<Option<String>, String> {
+        Self::parse_with(key, value, \"url\", |value| {
+            let value = value.trim();
+            (value.contains(\"://\") && !value.contains(char::is_whitespace)).then(|| value.to_string())
+        })
+    }
+
+    fn parse_with<T>(key: &str, value: &str, type_name: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>, String> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        parse(value)
+            .map(Some)
+            .ok_or_else(|| format!(\"{}: '{}' is not a valid {}\", key, value, type_name))
+    }
+";
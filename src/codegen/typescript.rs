@@ -0,0 +1,248 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::{camel_case, quote, Field, Spec};
+use crate::models::ItemType;
+
+fn ts_type(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "string",
+        ItemType::Url => "string | undefined",
+        ItemType::Integer | ItemType::Number => "number | undefined",
+        ItemType::Boolean => "boolean | undefined",
+    }
+}
+
+fn parser(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::String => "parseString",
+        ItemType::Integer => "parseInteger",
+        ItemType::Number => "parseNumber",
+        ItemType::Boolean => "parseBoolean",
+        ItemType::Url => "parseUrl",
+    }
+}
+
+/// Writes a JSDoc comment, on one line when the text has one line.
+fn doc_comment(out: &mut String, indent: &str, lines: &[String]) {
+    let lines: Vec<String> = lines.iter().map(|line| line.replace("*/", "*\\/")).collect();
+    match lines.as_slice() {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "{}/** {} */", indent, line);
+        }
+        _ => {
+            let _ = writeln!(out, "{}/**", indent);
+            for line in &lines {
+                if line.is_empty() {
+                    let _ = writeln!(out, "{} *", indent);
+                } else {
+                    let _ = writeln!(out, "{} * {}", indent, line);
+                }
+            }
+            let _ = writeln!(out, "{} */", indent);
+        }
+    }
+}
+
+fn field_doc(field: &Field) -> Vec<String> {
+    let mut lines: Vec<String> = field
+        .description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if field.secret {
+        lines.push(String::new());
+        lines.push("Secret: do not log this value.".to_string());
+    }
+    lines
+}
+
+/// Generates an interface with one typed property per app item, and a loader that reads the
+/// environment.
+///
+/// Only the value checks the schema uses are emitted, so the module passes `noUnusedLocals`.
+pub(super) fn generate(spec: &Spec) -> String {
+    let name = &spec.type_name;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Generated by rpcfg from {}. Do not edit; run `rpcfg codegen --lang typescript` to update.",
+        spec.source
+    );
+    out.push('\n');
+
+    doc_comment(
+        &mut out,
+        "",
+        &[format!("The settings of the {}/{} configuration.", spec.project, spec.config_name)],
+    );
+    let _ = writeln!(out, "export interface {} {{", name);
+    for field in &spec.fields {
+        doc_comment(&mut out, "  ", &field_doc(field));
+        let _ = writeln!(out, "  {}: {};", camel_case(&field.name), ts_type(field.item_type));
+    }
+    out.push_str("}\n\n");
+
+    doc_comment(
+        &mut out,
+        "",
+        &[
+            "Reads the settings from the environment: each item's `temp_environment_variable_name`,".to_string(),
+            "then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.".to_string(),
+            String::new(),
+            "Throws an Error naming the setting when a value does not fit its type.".to_string(),
+        ],
+    );
+    // Without fields nothing reads the environment, and an unused name fails `noUnusedLocals`
+    let env = if spec.fields.is_empty() { "_env" } else { "env" };
+    let _ = writeln!(
+        out,
+        "export function load{}({}: Record<string, string | undefined> = process.env): {} {{",
+        name, env, name
+    );
+    let secrets: Vec<String> = spec
+        .fields
+        .iter()
+        .filter(|field| field.secret)
+        .map(|field| quote(&camel_case(&field.name)))
+        .collect();
+    if secrets.is_empty() {
+        out.push_str("  return {\n");
+    } else {
+        out.push_str("  return maskSecrets({\n");
+    }
+    for field in &spec.fields {
+        let vars: Vec<String> = field.env_vars.iter().map(|var| quote(var)).collect();
+        let _ = writeln!(
+            out,
+            "    {}: {}({}, readEnv(env, [{}])),",
+            camel_case(&field.name),
+            parser(field.item_type),
+            quote(&field.key),
+            vars.join(", ")
+        );
+    }
+    if secrets.is_empty() {
+        out.push_str("  };\n}\n");
+    } else {
+        let _ = writeln!(out, "  }}, [{}]);\n}}", secrets.join(", "));
+    }
+
+    if !spec.fields.is_empty() {
+        out.push_str(READ_ENV);
+    }
+    if !secrets.is_empty() {
+        out.push_str(MASK_SECRETS);
+    }
+    let used: BTreeSet<&str> = spec.fields.iter().map(|field| parser(field.item_type)).collect();
+    for (parser, code) in HELPERS {
+        if used.contains(parser) {
+            out.push_str(code);
+        }
+    }
+    out
+}
+
+const READ_ENV: &str = "
+function readEnv(env: Record<string, string | undefined>, names: readonly string[]): string {
+  for (const name of names) {
+    const value = env[name];
+    if (value !== undefined) {
+      return value;
+    }
+  }
+  return \"\";
+}
+";
+
+/// Hides secret values from `JSON.stringify`, `String()` and `console.log`. The hooks are
+/// not enumerable, so they do not show up as settings.
+const MASK_SECRETS: &str = "
+function maskSecrets<T extends object>(settings: T, secrets: readonly string[]): T {
+  const masked = (): Record<string, unknown> =>
+    Object.fromEntries(
+      Object.entries(settings).map(([key, value]) => [key, secrets.includes(key) ? \"***\" : value]),
+    );
+  Object.defineProperties(settings, {
+    toJSON: { value: masked },
+    toString: { value: () => JSON.stringify(masked()) },
+    [Symbol.for(\"nodejs.util.inspect.custom\")]: { value: masked },
+  });
+  return settings;
+}
+";
+
+/// The value checks, matching `ItemType::check`, in the order they are emitted.
+const HELPERS: &[(&str, &str)] = &[
+    (
+        "parseString",
+        "
+function parseString(_key: string, value: string): string {
+  return value;
+}
+",
+    ),
+    (
+        "parseInteger",
+        "
+function parseInteger(key: string, value: string): number | undefined {
+  if (value === \"\") {
+    return undefined;
+  }
+  const number = Number(value);
+  // Beyond 2^53 a number cannot hold every integer, so the value would silently change
+  if (!/^[+-]?[0-9]+$/.test(value) || !Number.isSafeInteger(number)) {
+    throw new Error(`${key}: '${value}' is not a valid integer`);
+  }
+  return number;
+}
+",
+    ),
+    (
+        "parseNumber",
+        "
+function parseNumber(key: string, value: string): number | undefined {
+  if (value === \"\") {
+    return undefined;
+  }
+  const number = Number(value);
+  if (value.trim() !== value || !Number.isFinite(number)) {
+    throw new Error(`${key}: '${value}' is not a valid number`);
+  }
+  return number;
+}
+",
+    ),
+    (
+        "parseBoolean",
+        "
+function parseBoolean(key: string, value: string): boolean | undefined {
+  if (value === \"\") {
+    return undefined;
+  }
+  if (value !== \"true\" && value !== \"false\") {
+    throw new Error(`${key}: '${value}' is not a valid boolean`);
+  }
+  return value === \"true\";
+}
+",
+    ),
+    (
+        "parseUrl",
+        "
+function parseUrl(key: string, value: string): string | undefined {
+  if (value === \"\") {
+    return undefined;
+  }
+  const url = value.trim();
+  if (!url.includes(\"://\") || /\\s/.test(url)) {
+    throw new Error(`${key}: '${value}' is not a valid url`);
+  }
+  return url;
+}
+",
+    ),
+];
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::codegen::{self, CodegenOptions, Lang};
+use crate::files::write_atomic;
+use crate::models::CommandResult;
+use crate::{Fail, Success};
+
+/// Generates typed code for reading the settings of a schema.
+///
+/// # Arguments
+///
+/// * `input_file` - The schema to generate code for.
+/// * `lang` - The language to generate.
+/// * `options` - The name of the generated type.
+/// * `output_path` - The file to write the code to; the code goes to `output` when None.
+/// * `check` - Compare the code with `output_path` instead of writing it.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the code or messages.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+/// With `check`, the status is `Error` when the file is missing or out of date.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The schema cannot be loaded, or its keys cannot be turned into field names.
+/// * `check` is set without `output_path`.
+/// * The output file cannot be written.
+pub fn execute<R: BufRead, W: Write>(
+    input_file: &str,
+    lang: Lang,
+    options: &CodegenOptions,
+    output_path: Option<&str>,
+    check: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let code = codegen::generate_file(input_file, lang, options)?;
+
+    match (output_path, check) {
+        (None, true) => anyhow::bail!("--check needs the file to compare with, given with -o"),
+        (None, false) => {
+            write!(output, "{}", code)?;
+            Ok(Success!("Generated code for {}.", input_file))
+        }
+        (Some(path), true) => {
+            if fs::read_to_string(path).ok().as_deref() == Some(code.as_str()) {
+                writeln!(output, "{} is up to date.", path)?;
+                Ok(Success!("{} is up to date.", path))
+            } else {
+                writeln!(output, "{} is out of date with {}; run codegen again.", path, input_file)?;
+                Ok(Fail!("{} is out of date.", path))
+            }
+        }
+        (Some(path), false) => {
+            write_atomic(Path::new(path), &code).with_context(|| format!("Failed to write {}", path))?;
+            writeln!(output, "Generated {} from {}", path, input_file)?;
+            info!("Generated {} from {}", path, input_file);
+            Ok(Success!("Generated {}.", path))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Status;
+    use crate::safe_test;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    safe_test!(test_codegen_check, {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.rs");
+        let path = path.to_str().unwrap();
+        let schema = "repo_config.json";
+        let options = CodegenOptions::default();
+
+        let result = execute(schema, Lang::Rust, &options, Some(path), true, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(matches!(result.status, Status::Error));
+
+        execute(schema, Lang::Rust, &options, Some(path), false, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(fs::read_to_string(path)?.contains("pub struct RpcfgConfig"));
+        let result = execute(schema, Lang::Rust, &options, Some(path), true, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(matches!(result.status, Status::Ok));
+
+        let renamed = CodegenOptions {
+            type_name: Some("Settings".to_string()),
+        };
+        let result = execute(schema, Lang::Rust, &renamed, Some(path), true, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(matches!(result.status, Status::Error));
+
+        Ok(())
+    });
+}
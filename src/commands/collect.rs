@@ -1,27 +1,29 @@
 use anyhow::Context;
 use std::collections::HashMap;
 use std::fmt::Write as FmtWrite; // Add this import at the top of the file
-use std::fs;
-use std::io::{BufRead, Write};
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::Path;
 
 use tabwriter::TabWriter;
 use tracing::debug;
 
 use crate::models::{CommandResult, Config, ConfigItem};
+use crate::tui::Outcome;
+use crate::undo::{Change, UndoStack};
 use crate::{env_output_uri, json_output_uri, Success};
 
 /// Executes the collect command, gathering configuration input from the user.
 ///
 /// This function serves as the entry point for the collect command. It checks if the
 /// configuration needs updating based on file timestamps (unless ignore_timestamps is true),
-/// and if so, it calls `collect_user_input` to handle the actual collection of configuration data.
+/// and if so, it calls `collect_user_input` (or `collect_with_tui`) to handle the actual collection of configuration data.
 ///
 /// # Arguments
 ///
 /// * `config` - A mutable reference to the Config object to be updated.
 /// * `input_file` - The path to the input file.
 /// * `ignore_timestamps` - Whether to ignore timestamp checks and always collect.
+/// * `tui` - Whether to use the full-screen terminal UI. Ignored unless stdin and stdout are terminals.
 /// * `input` - A mutable reference to a BufRead trait object for reading user input.
 /// * `output` - A mutable reference to a Write trait object for writing prompts and messages.
 ///
@@ -38,6 +40,7 @@ pub fn execute(
     config: &mut crate::Config,
     input_file: &str,
     ignore_timestamps: bool,
+    tui: bool,
     input: &mut impl BufRead,
     output: &mut impl Write,
 ) -> anyhow::Result<crate::CommandResult> {
@@ -73,10 +76,52 @@ pub fn execute(
         }
     }
 
+    let _lock = crate::files::lock_project(config)?;
+
+    if tui {
+        if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+            return collect_with_tui(config);
+        }
+        debug!("Not running in a terminal, using the line-based interface");
+    }
+
     let result = collect_user_input(config, input, output)?;
 
     Ok(result)
 }
+
+/// Collects configuration values with the full-screen terminal UI.
+///
+/// This is the `--tui` counterpart of `collect_user_input`: values are initialized from
+/// their defaults, edited on screen, and saved if the user chooses to.
+///
+/// # Arguments
+///
+/// * `config` - A mutable reference to the Config object to be updated.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+pub fn collect_with_tui(config: &mut Config) -> anyhow::Result<CommandResult> {
+    initialize_config_values(config);
+    config.validate_rpcfg_config()?;
+    config.mark_clean();
+
+    let mut result = match crate::tui::run(config)? {
+        Outcome::Save => {
+            save_configuration(config, config.schema_changed)?;
+            config.mark_clean();
+            Success!("Configuration saved.")
+        }
+        Outcome::Quit => Success!("Configuration collected successfully."),
+    };
+
+    set_environment_variables(config);
+
+    result.env_file = env_output_uri!(config);
+    result.json_file = json_output_uri!(config);
+    Ok(result)
+}
 /// Collects user input to configure items in the provided Config object.
 ///
 /// This function initializes config values with defaults if
@@ -116,7 +161,9 @@ pub fn execute(
 ///         temp_environment_variable_name: "TEST_ITEM_1".to_string(),
 ///         required_as_env: true,
 ///         value: "".to_string(),
+///         ..Default::default()
 ///     });
+///     # config.is_test = true; // save under the temp directory
 ///     let mut input = Cursor::new("6\nnew_value\ns\nq\n");
 ///     let mut output = Vec::new();
 ///     let result = collect_user_input(&mut config, &mut input, &mut output)?;
@@ -164,8 +211,9 @@ fn initialize_config_values(config: &mut Config) {
 /// Handles the interactive configuration loop
 ///
 /// This function manages the interactive session where the user can view,
-/// update, add new settings, and save configuration items. It continues to prompt the user
-/// for actions until they choose to quit or save.
+/// update, add, delete, edit, move and save configuration items. It continues to prompt the user
+/// for actions until they choose to quit or save. Changes to the settings themselves (as opposed
+/// to their values) are written back to the input file on save.
 ///
 /// # Arguments
 ///
@@ -182,63 +230,254 @@ fn interactive_config_loop<R: BufRead, W: Write>(
     input: &mut R,
     output: &mut W,
 ) -> anyhow::Result<()> {
-    let mut new_setting_added = false;
+    config.validate_rpcfg_config()?;
+    config.mark_clean();
+    let mut filter = String::new();
+    let mut changes = UndoStack::new();
 
     loop {
         config.validate_rpcfg_config()?;
-        show_current_config(config, output)?;
+        show_filtered_config(config, &filter, output)?;
 
         write!(
             output,
-            "\nEnter item number to update, 'S' to save, 'N' to add a new setting, or 'Q' to quit: "
+            "\nEnter item number or key to update, '/pattern' to filter, 'S' to save, 'N' to add a new setting, 'D' to delete, 'E' to edit, 'M' to move, 'U' to undo, 'R' to redo, 'H' for history, 'P' to preview changes, or 'Q' to quit: "
         )?;
         output.flush()?;
 
-        let user_input = read_user_input(input)?;
+        // End of input is treated as a request to quit
+        let user_input = read_user_line(input)?.unwrap_or_else(|| "q".to_string());
 
-        match user_input.as_str() {
+        let change = match user_input.as_str() {
             "s" | "S" => {
-                save_configuration(config, new_setting_added)?;
+                save_configuration(config, config.schema_changed)?;
+                config.mark_clean();
                 writeln!(output, "Configuration saved.")?;
                 break;
             }
-            "q" | "Q" => break,
+            "q" | "Q" => {
+                if confirm_quit(config, input, output)? {
+                    break;
+                }
+                None
+            }
             "n" | "N" => {
                 add_new_setting(config, input, output)?;
-                new_setting_added = true;
+                config.app.last().map(|item| Change::Added {
+                    position: config.app.len() - 1,
+                    item: item.clone(),
+                })
+            }
+            "d" | "D" => delete_setting(config, input, output)?,
+            "e" | "E" => edit_setting(config, input, output)?,
+            "m" | "M" => move_setting(config, input, output)?,
+            "u" | "U" => {
+                match changes.undo(config) {
+                    Some(change) => {
+                        config.schema_changed |= change.changes_schema();
+                        writeln!(output, "Undid: {}", change.describe())?;
+                    }
+                    None => writeln!(output, "Nothing to undo.")?,
+                }
+                None
+            }
+            "r" | "R" => {
+                match changes.redo(config) {
+                    Some(change) => {
+                        config.schema_changed |= change.changes_schema();
+                        writeln!(output, "Redid: {}", change.describe())?;
+                    }
+                    None => writeln!(output, "Nothing to redo.")?,
+                }
+                None
+            }
+            "h" | "H" | "history" => {
+                show_history(&changes, output)?;
+                None
+            }
+            "p" | "P" => {
+                show_pending_changes(config, output)?;
+                None
+            }
+            _ if user_input.starts_with('/') => {
+                filter = user_input[1..].trim().to_string();
+                None
             }
             _ => handle_item_update(config, &user_input, input, output)?,
+        };
+
+        if let Some(change) = change {
+            config.schema_changed |= change.changes_schema();
+            changes.record(change);
         }
     }
     Ok(())
 }
 
+/// Writes every change made in the session, oldest first, with secret values masked.
+fn show_history<W: Write>(changes: &UndoStack, output: &mut W) -> anyhow::Result<()> {
+    if changes.history().is_empty() {
+        writeln!(output, "No changes in this session.")?;
+        return Ok(());
+    }
+    writeln!(output, "Session history:")?;
+    for (number, entry) in changes.history().iter().enumerate() {
+        writeln!(output, "  {}. {}", number + 1, entry)?;
+    }
+    Ok(())
+}
+
+/// Asks what to do with unsaved changes before quitting.
+///
+/// Offers to save, discard, cancel or preview the pending changes. Saving writes the
+/// output files (and the input file if settings changed) just like 'S'.
+///
+/// # Returns
+///
+/// Returns `true` if the loop should exit, or `false` if the user cancelled.
+fn confirm_quit<R: BufRead, W: Write>(
+    config: &mut Config,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<bool> {
+    if !config.is_dirty() {
+        return Ok(true);
+    }
+    loop {
+        write!(
+            output,
+            "You have unsaved changes. (S)ave, (D)iscard, (C)ancel, or (P)review changes: "
+        )?;
+        output.flush()?;
+        let Some(answer) = read_user_line(input)? else {
+            writeln!(output, "\nNo more input; unsaved changes discarded.")?;
+            return Ok(true);
+        };
+        match answer.to_lowercase().as_str() {
+            "s" | "save" => {
+                save_configuration(config, config.schema_changed)?;
+                config.mark_clean();
+                writeln!(output, "Configuration saved.")?;
+                return Ok(true);
+            }
+            "d" | "discard" => {
+                writeln!(output, "Unsaved changes discarded.")?;
+                return Ok(true);
+            }
+            "c" | "cancel" => return Ok(false),
+            "p" | "preview" => show_pending_changes(config, output)?,
+            _ => writeln!(output, "Invalid input. Please try again.")?,
+        }
+    }
+}
+
+/// Writes the changes that have not been saved yet.
+pub fn show_pending_changes<W: Write>(config: &Config, output: &mut W) -> anyhow::Result<()> {
+    let changes = config.pending_changes();
+    if changes.is_empty() && !config.schema_changed {
+        writeln!(output, "No pending changes.")?;
+        return Ok(());
+    }
+    writeln!(output, "Pending changes:")?;
+    for change in &changes {
+        writeln!(output, "  {}", change.describe(config.is_secret(&change.key)))?;
+    }
+    if config.schema_changed {
+        writeln!(output, "  (settings changed; the input file will be updated)")?;
+    }
+    Ok(())
+}
+
 /// Read and trim user input
 fn read_user_input<R: BufRead>(input: &mut R) -> anyhow::Result<String> {
+    Ok(read_user_line(input)?.unwrap_or_default())
+}
+
+/// Read and trim a line of user input, returning None at the end of the input
+fn read_user_line<R: BufRead>(input: &mut R) -> anyhow::Result<Option<String>> {
     let mut user_input = String::new();
-    input
+    let bytes = input
         .read_line(&mut user_input)
         .context("Failed to read user input")?;
-    Ok(user_input.trim().to_string()) // Remove .to_lowercase()
+    if bytes == 0 {
+        return Ok(None);
+    }
+    Ok(Some(user_input.trim().to_string())) // Remove .to_lowercase()
 }
 
 /// Handle updating a specific item in the configuration
+///
+/// The item can be selected by its number in the table or by its key.
+///
+/// Returns the change made, or `None` if the input did not select an item or the value is unchanged.
 fn handle_item_update<R: BufRead, W: Write>(
     config: &mut Config,
     user_input: &str,
     input: &mut R,
     output: &mut W,
-) -> anyhow::Result<()> {
-    if let Ok(index) = user_input.parse::<usize>() {
-        if index > 0 && index <= config.rpcfg.len() + config.app.len() {
-            update_item(config, index - 1, input, output)?;
-        } else {
-            writeln!(output, "Invalid item number. Please try again.")?;
+) -> anyhow::Result<Option<Change>> {
+    let Some(index) = select_item(config, user_input, output)? else {
+        return Ok(None);
+    };
+    let before = nth_item(config, index).clone();
+    update_item(config, index, input, output)?;
+    let after = nth_item(config, index);
+    if after.value == before.value {
+        return Ok(None);
+    }
+    if let Err(e) = after.validate_value(&after.value) {
+        // Keep the previous value rather than saving one that does not fit the item
+        writeln!(output, "Invalid value for {}: {}", before.key, e)?;
+        if let Some(item) = config.rpcfg.iter_mut().chain(config.app.iter_mut()).nth(index) {
+            *item = before;
         }
-    } else {
-        writeln!(output, "Invalid input. Please try again.")?;
+        return Ok(None);
     }
-    Ok(())
+    Ok(Some(Change::Value {
+        key: before.key,
+        before: before.value,
+        after: after.value.clone(),
+        secret: before.secret,
+    }))
+}
+
+/// Returns the item at an index across the rpcfg and app items.
+fn nth_item(config: &Config, index: usize) -> &ConfigItem {
+    config
+        .rpcfg
+        .iter()
+        .chain(config.app.iter())
+        .nth(index)
+        .expect("selected item index is in range")
+}
+
+/// Finds the item selected by a number from the table or by key.
+///
+/// Returns the index across the rpcfg and app items, or writes an error message and
+/// returns `None` if nothing matches.
+fn select_item<W: Write>(
+    config: &Config,
+    selector: &str,
+    output: &mut W,
+) -> anyhow::Result<Option<usize>> {
+    let count = config.rpcfg.len() + config.app.len();
+    if let Ok(index) = selector.parse::<usize>() {
+        if index > 0 && index <= count {
+            return Ok(Some(index - 1));
+        }
+        writeln!(output, "Invalid item number. Please try again.")?;
+        return Ok(None);
+    }
+    if let Some(index) = config
+        .rpcfg
+        .iter()
+        .chain(config.app.iter())
+        .position(|item| item.key == selector)
+    {
+        return Ok(Some(index));
+    }
+    writeln!(output, "Invalid input. Please try again.")?;
+    Ok(None)
 }
 
 /// Set environment variables for required items
@@ -264,6 +503,29 @@ fn set_environment_variables(config: &Config) {
 /// Returns a Result, which is Ok if the configuration is successfully written to the output,
 /// or an error if any I/O operations fail.
 pub fn show_current_config<W: Write>(config: &Config, output: &mut W) -> anyhow::Result<()> {
+    show_filtered_config(config, "", output)
+}
+
+/// Displays the configuration items whose key or description contains `filter`
+///
+/// Rows keep the index they have in the full table, so an item can be selected by the
+/// same number whether or not a filter is active. An empty filter shows every item.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the Config object to be displayed.
+/// * `filter` - The case-insensitive pattern to match against keys and descriptions.
+/// * `output` - A mutable reference to a Write trait object for writing the configuration.
+///
+/// # Returns
+///
+/// Returns a Result, which is Ok if the configuration is successfully written to the output,
+/// or an error if any I/O operations fail.
+pub fn show_filtered_config<W: Write>(
+    config: &Config,
+    filter: &str,
+    output: &mut W,
+) -> anyhow::Result<()> {
     if config.is_test {
         writeln!(output, "(Test mode)")?;
     }
@@ -271,24 +533,45 @@ pub fn show_current_config<W: Write>(config: &Config, output: &mut W) -> anyhow:
 
     let mut tw = TabWriter::new(vec![]);
 
-    writeln!(tw, "Index\tDescription\tValue")?;
-    writeln!(tw, "-----\t-----------\t-----")?;
+    writeln!(tw, "Index\tKey\tDescription\tValue")?;
+    writeln!(tw, "-----\t---\t-----------\t-----")?;
 
+    let mut shown = 0;
+    let total = config.rpcfg.len() + config.app.len();
     for (index, item) in config.rpcfg.iter().chain(config.app.iter()).enumerate() {
+        if !item.matches(filter) {
+            continue;
+        }
+        shown += 1;
         let display_value = if item.value.is_empty() {
-            &item.default
+            item.masked(&item.default)
         } else {
-            &item.value
+            item.masked(&item.value)
         };
-        writeln!(tw, "{}\t{}\t{}", index + 1, item.description, display_value)?;
+        writeln!(
+            tw,
+            "{}\t{}\t{}\t{}",
+            index + 1,
+            item.key,
+            item.description,
+            display_value
+        )?;
     }
     tw.flush()?;
 
     output.write_all(&tw.into_inner()?)?;
+    if !filter.is_empty() {
+        writeln!(
+            output,
+            "(showing {} of {} settings matching '{}'; enter '/' to clear)",
+            shown, total, filter
+        )?;
+    }
     writeln!(output)?; // Add an extra newline at the end
 
     Ok(())
 }
+
 /// Updates a specific item in the configuration based on user input.
 ///
 /// This function prompts the user to enter a new value for a specific configuration item,
@@ -327,6 +610,7 @@ pub fn show_current_config<W: Write>(config: &Config, output: &mut W) -> anyhow:
 ///                 temp_environment_variable_name: "APP_TEST_ITEM_1".to_string(),
 ///                 required_as_env: true,
 ///                 value: "old_value".to_string(),
+///                 ..Default::default()
 ///             });
 ///     
 ///     // Update the first app item (index 5, assuming 5 rpcfg items)
@@ -350,12 +634,14 @@ pub fn update_item<R: BufRead, W: Write>(
     write!(
         output,
         "Enter new value for {} (current: {}): ",
-        item.description, item.value
+        item.description,
+        item.masked(&item.value)
     )?;
     output.flush()?;
     let mut new_value = String::new();
     input.read_line(&mut new_value)?;
     item.value = new_value.trim().to_string();
+    item.source = crate::models::ValueSource::Collected;
     debug!("Updated item: {:?}", item);
     Ok(())
 }
@@ -381,6 +667,7 @@ pub fn update_item<R: BufRead, W: Write>(
 /// This function will return an error if:
 /// * There's an I/O error when creating the output directory or writing to either the JSON or ENV file.
 /// * The `base_output_dir` function fails to generate a valid directory path.
+/// * `stored` is `repo` and `repo_dir` is absolute or leaves the directory of the input file.
 /// * The configuration data cannot be serialized to JSON.
 ///
 /// # Examples
@@ -399,9 +686,11 @@ pub fn update_item<R: BufRead, W: Write>(
 ///         temp_environment_variable_name: "TEST_ITEM_1".to_string(),
 ///         required_as_env: true,
 ///         value: "value1".to_string(),
+///         ..Default::default()
 ///     });
 ///
-///     save_configuration(&config)?;
+///     # config.is_test = true; // save under the temp directory
+///     save_configuration(&config, false)?;
 ///     Ok(())
 /// }
 /// ```
@@ -410,14 +699,30 @@ pub fn update_item<R: BufRead, W: Write>(
 ///
 /// This function will create the output directory if it doesn't exist and
 /// will overwrite existing files if they already exist at the target paths.
+/// The JSON and ENV files are each written atomically, and if the ENV file cannot be
+/// replaced the JSON file is restored. The ENV file is stamped with a hash of the JSON
+/// file, so a pair left from different saves by a crash between the two renames is
+/// refused by `fetch` and reported by `doctor`; collecting again writes a matching pair. On Unix, new
+/// directories are created with mode 0700 and the output files with mode 0600.
+/// Each save also records a snapshot of both files (see `snapshots::record`).
 pub fn save_configuration(config: &Config, save_input: bool) -> anyhow::Result<()> {
+    let is_repo = config.get_settings("stored").iter().any(|item| item.value == "repo");
+    if is_repo {
+        // Name the problem instead of failing on the missing output directory below
+        crate::rp_macros::repo_dir(config)?;
+    }
     let base_dir = crate::rp_macros::base_output_dir(config)
         .ok_or_else(|| anyhow::anyhow!("Failed to get base output directory"))?;
 
     debug!("Base output directory: {:?}", base_dir);
 
-    // Create the base directory if it doesn't exist
-    fs::create_dir_all(&base_dir)?;
+    // Create the base directory if it doesn't exist, readable by the owner only
+    crate::files::create_private_dir(&base_dir)?;
+
+    // Outputs stored next to the input file must stay out of git
+    if is_repo {
+        crate::files::ensure_gitignored(config)?;
+    }
 
     let json_path = base_dir.with_extension("json");
     let env_path = base_dir.with_extension("env");
@@ -429,36 +734,52 @@ pub fn save_configuration(config: &Config, save_input: bool) -> anyhow::Result<(
     let mut flat_json: HashMap<String, String> = HashMap::new();
     for item in config.rpcfg.iter().chain(config.app.iter()) {
         if item.key != "is_test" {
-            debug!("Adding item to JSON: {} = {}", item.key, item.value);
+            debug!("Adding item to JSON: {} = {}", item.key, item.masked(&item.value));
             flat_json.insert(item.key.clone(), item.value.clone());
         }
     }
 
-    // Save JSON file
     let json_content = serde_json::to_string_pretty(&flat_json)?;
-    fs::write(&json_path, json_content)?;
 
     // Save ENV file
     let mut env_content = String::new();
     for item in config.rpcfg.iter().chain(config.app.iter()) {
         if item.required_as_env {
-            debug!("Saving to ENV file: {} = {}", item.key, item.value);
+            debug!("Saving to ENV file: {} = {}", item.key, item.masked(&item.value));
             writeln!(env_content, "{}={}", item.key.to_uppercase(), item.value)?;
             // Note: We're only uppercasing the key, not the value
         }
     }
-    fs::write(&env_path, &env_content)?;
+
+    // Tie the ENV file to this JSON file, so a pair torn by a crash can be detected
+    let env_content = crate::files::stamp_env(&json_content, &env_content);
+
+    // Replace both files, restoring the JSON file if the ENV file cannot be written
+    crate::files::write_all_or_none(&[
+        (json_path.clone(), json_content.clone()),
+        (env_path, env_content.clone()),
+    ])?;
 
     debug!("Configuration saved successfully");
     debug!("ENV content: {}", env_content);
 
+    // Let `list` find outputs stored next to the input file; they are saved either way
+    if is_repo {
+        if let Err(e) = crate::commands::list::register_repo_output(config, &json_path) {
+            debug!("Cannot record the repo output for list: {:#}", e);
+        }
+    }
+
+    // Keep a snapshot so the save can be rolled back
+    crate::snapshots::record(config, &json_content, &env_content)?;
+
     // Save input file if save_input is true and input_file is specified
     if save_input {
         let input_file_path = &config.input_file;
         if !input_file_path.is_empty() {
             debug!("Updating input file: {}", input_file_path);
-            let input_content = serde_json::to_string_pretty(&config)?;
-            fs::write(input_file_path, input_content)
+            let input_content = serde_json::to_string_pretty(&config.input_file_view())?;
+            crate::files::write_atomic(Path::new(input_file_path), &input_content)
                 .with_context(|| format!("Failed to update input file: {}", input_file_path))?;
             debug!("Input file updated successfully");
         } else {
@@ -547,6 +868,7 @@ pub fn add_new_setting<R: BufRead, W: Write>(
         temp_environment_variable_name,
         required_as_env,
         value: default,
+        ..Default::default()
     };
 
     config.app.push(new_item);
@@ -555,15 +877,200 @@ pub fn add_new_setting<R: BufRead, W: Write>(
     Ok(())
 }
 
+/// Prompts for an item number or key and returns its index across the rpcfg and app items.
+///
+/// Writes an error message and returns `None` if the input does not select an item.
+fn read_item_index<R: BufRead, W: Write>(
+    config: &Config,
+    prompt: &str,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<Option<usize>> {
+    write!(output, "{}", prompt)?;
+    output.flush()?;
+    let user_input = read_user_input(input)?;
+    select_item(config, &user_input, output)
+}
+
+/// Returns the position in `config.app` of a combined index, or writes why the item cannot be changed.
+fn app_position<W: Write>(
+    config: &Config,
+    index: usize,
+    action: &str,
+    output: &mut W,
+) -> anyhow::Result<Option<usize>> {
+    if index < config.rpcfg.len() {
+        writeln!(output, "rpcfg settings cannot be {}.", action)?;
+        return Ok(None);
+    }
+    Ok(Some(index - config.rpcfg.len()))
+}
+
+/// Deletes an app setting interactively, after confirmation.
+///
+/// rpcfg settings cannot be deleted, and settings inherited through `extends` or `include`
+/// must be deleted from the file that defines them.
+///
+/// # Returns
+///
+/// Returns the change made, or `None` if no setting was deleted.
+pub fn delete_setting<R: BufRead, W: Write>(
+    config: &mut Config,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<Option<Change>> {
+    let Some(index) = read_item_index(config, "Enter item number to delete: ", input, output)?
+    else {
+        return Ok(None);
+    };
+    let Some(position) = app_position(config, index, "deleted", output)? else {
+        return Ok(None);
+    };
+    let item = &config.app[position];
+    if !item.defined_in.is_empty() && item.defined_in != config.input_file {
+        writeln!(
+            output,
+            "'{}' is defined in {}; delete it there.",
+            item.key, item.defined_in
+        )?;
+        return Ok(None);
+    }
+
+    write!(output, "Delete '{}'? (y/N): ", item.key)?;
+    output.flush()?;
+    if !matches!(read_user_input(input)?.to_lowercase().as_str(), "y" | "yes") {
+        writeln!(output, "Delete cancelled.")?;
+        return Ok(None);
+    }
+
+    let removed = config.app.remove(position);
+    debug!("Deleted item: {:?}", removed);
+    writeln!(output, "Setting '{}' deleted.", removed.key)?;
+    Ok(Some(Change::Deleted {
+        position,
+        item: removed,
+    }))
+}
+
+/// Edits the metadata of a setting interactively.
+///
+/// Prompts for the description, default value, environment variable name and
+/// required_as_env flag, showing the current value of each. An empty answer keeps
+/// the current value and '-' clears the environment variable name. An inherited
+/// setting that is edited becomes an override in the input file.
+///
+/// # Returns
+///
+/// Returns the change made, or `None` if no setting was edited.
+pub fn edit_setting<R: BufRead, W: Write>(
+    config: &mut Config,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<Option<Change>> {
+    let Some(index) = read_item_index(config, "Enter item number to edit: ", input, output)?
+    else {
+        return Ok(None);
+    };
+    let input_file = config.input_file.clone();
+    let item = config
+        .rpcfg
+        .iter_mut()
+        .chain(config.app.iter_mut())
+        .nth(index)
+        .ok_or(anyhow::anyhow!("Item not found"))?;
+    let before = item.clone();
+
+    writeln!(output, "Editing '{}' (press Enter to keep the current value):", item.key)?;
+
+    write!(output, "Description (current: {}): ", item.description)?;
+    output.flush()?;
+    let description = read_user_input(input)?;
+    if !description.is_empty() {
+        item.description = description;
+    }
+
+    write!(output, "Default value (current: {}): ", item.default)?;
+    output.flush()?;
+    let default = read_user_input(input)?;
+    if !default.is_empty() {
+        item.default = default;
+    }
+
+    write!(
+        output,
+        "Environment variable name (current: {}, '-' to clear): ",
+        item.temp_environment_variable_name
+    )?;
+    output.flush()?;
+    let env_name = read_user_input(input)?;
+    if env_name == "-" {
+        item.temp_environment_variable_name.clear();
+    } else if !env_name.is_empty() {
+        item.temp_environment_variable_name = env_name;
+    }
+
+    write!(
+        output,
+        "Is this required as an environment variable? (current: {}, y/n): ",
+        if item.required_as_env { "y" } else { "n" }
+    )?;
+    output.flush()?;
+    match read_user_input(input)?.to_lowercase().as_str() {
+        "y" => item.required_as_env = true,
+        "n" => item.required_as_env = false,
+        _ => {}
+    }
+
+    item.defined_in = input_file;
+    debug!("Edited item: {:?}", item);
+    writeln!(output, "Setting '{}' updated.", item.key)?;
+    Ok(Some(Change::Edited {
+        index,
+        before,
+        after: item.clone(),
+    }))
+}
+
+/// Moves an app setting to a new position interactively.
+///
+/// Both the item and the new position are given as item numbers from the table;
+/// rpcfg settings keep their place ahead of the app settings.
+///
+/// # Returns
+///
+/// Returns the change made, or `None` if no setting was moved.
+pub fn move_setting<R: BufRead, W: Write>(
+    config: &mut Config,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<Option<Change>> {
+    let Some(from) = read_item_index(config, "Enter item number to move: ", input, output)? else {
+        return Ok(None);
+    };
+    let Some(from) = app_position(config, from, "moved", output)? else {
+        return Ok(None);
+    };
+    let Some(to) = read_item_index(config, "Enter new position: ", input, output)? else {
+        return Ok(None);
+    };
+    let Some(to) = app_position(config, to, "displaced", output)? else {
+        return Ok(None);
+    };
+
+    let item = config.app.remove(from);
+    writeln!(output, "Setting '{}' moved to position {}.", item.key, config.rpcfg.len() + to + 1)?;
+    let key = item.key.clone();
+    config.app.insert(to, item);
+    Ok(Some(Change::Moved { key, from, to }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::commands::init;
     use crate::{create_test_input_file, parse_config_file};
     use crate::{models::ConfigItem, safe_test, test_utils::create_test_config};
     use std::fs;
     use std::io::Cursor;
-    use tempfile::TempDir;
     use uuid::Uuid;
 
     safe_test!(test_non_interactive_mode, {
@@ -703,6 +1210,7 @@ mod tests {
                     temp_environment_variable_name: format!("{}_{}", key.to_uppercase(), test_id),
                     required_as_env: *required_as_env,
                     value: value.clone(),
+                    ..Default::default()
                 });
             }
         }
@@ -802,6 +1310,145 @@ mod tests {
         Ok(())
     });
 
+    safe_test!(test_delete_edit_move_settings, {
+        let (mut config, _temp_dir) = create_test_input_file!("delete_edit_move");
+        let item1 = config.app[0].key.clone();
+        let item2 = config.app[1].key.clone();
+
+        // Add a setting, move it to the top of the app items, edit item 2, delete item 1, then save
+        let mut input = Cursor::new(
+            "n\nnew_key\nNew description\ndefault_value\n\nn\n\
+             m\n8\n6\n\
+             e\n8\nBetter description\n\nITEM2_ENV\ny\n\
+             d\n7\ny\n\
+             d\n1\n\
+             s\n",
+        );
+        let mut output = Cursor::new(Vec::new());
+        collect_user_input(&mut config, &mut input, &mut output)?;
+
+        let output_str = String::from_utf8(output.into_inner())?;
+        assert!(output_str.contains("rpcfg settings cannot be deleted."));
+
+        let keys: Vec<&str> = config.app.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, vec!["new_key", item2.as_str()]);
+        assert!(!keys.contains(&item1.as_str()));
+
+        // The changes are persisted to the input file
+        let updated_config = parse_config_file(&config.input_file)?;
+        let keys: Vec<&str> = updated_config.app.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, vec!["new_key", item2.as_str()]);
+        let edited = &updated_config.app[1];
+        assert_eq!(edited.description, "Better description");
+        assert_eq!(edited.default, "default2");
+        assert_eq!(edited.temp_environment_variable_name, "ITEM2_ENV");
+        assert!(edited.required_as_env);
+
+        Ok(())
+    });
+
+    safe_test!(test_quit_with_unsaved_changes, {
+        // Discarding leaves no output files behind
+        {
+            let test_id = Uuid::new_v4().to_string();
+            let mut config = create_test_config(&test_id);
+            let mut input = Cursor::new("6\nchanged\nq\nd\n");
+            let mut output = Cursor::new(Vec::new());
+            collect_user_input(&mut config, &mut input, &mut output)?;
+
+            let output_str = String::from_utf8(output.into_inner())?;
+            assert!(output_str.contains("You have unsaved changes."));
+            assert!(output_str.contains("Unsaved changes discarded."));
+            let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
+            assert!(fs::metadata(&json_path).is_err(), "Nothing should be saved");
+        }
+
+        // Preview, cancel, then save from the quit prompt
+        {
+            let test_id = Uuid::new_v4().to_string();
+            let mut config = create_test_config(&test_id);
+            let mut input = Cursor::new("6\nchanged\nq\np\nc\nq\ns\n");
+            let mut output = Cursor::new(Vec::new());
+            collect_user_input(&mut config, &mut input, &mut output)?;
+
+            let output_str = String::from_utf8(output.into_inner())?;
+            assert!(output_str.contains("'initial_value1' -> 'changed'"));
+            assert!(output_str.contains("Configuration saved."));
+            let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
+            let json_map: HashMap<String, String> =
+                serde_json::from_str(&fs::read_to_string(&json_path)?)?;
+            assert_eq!(json_map[&config.app[0].key], "changed");
+            fs::remove_file(json_path)?;
+        }
+
+        // Quitting without changes does not prompt
+        {
+            let test_id = Uuid::new_v4().to_string();
+            let mut config = create_test_config(&test_id);
+            let mut output = Cursor::new(Vec::new());
+            collect_user_input(&mut config, &mut Cursor::new("q\n"), &mut output)?;
+            let output_str = String::from_utf8(output.into_inner())?;
+            assert!(!output_str.contains("You have unsaved changes."));
+        }
+
+        Ok(())
+    });
+
+    safe_test!(test_filter_and_select_by_key, {
+        let test_id = Uuid::new_v4().to_string();
+        let mut config = create_test_config(&test_id);
+        let key = config.app[1].key.clone();
+
+        let mut input = Cursor::new(format!("/TEST ITEM 2\n{}\nby_key\n/\nnot_a_key\ns\n", key));
+        let mut output = Cursor::new(Vec::new());
+        collect_user_input(&mut config, &mut input, &mut output)?;
+
+        assert_eq!(config.app[1].value, "by_key");
+
+        let output_str = String::from_utf8(output.into_inner())?;
+        assert!(output_str.contains("(showing 1 of 7 settings matching 'TEST ITEM 2'"));
+        // The filtered row keeps its index from the full table
+        assert!(output_str.lines().any(|line| line.starts_with('7') && line.contains(&key)));
+        assert!(output_str.contains("Invalid input. Please try again."));
+
+        let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
+        fs::remove_file(json_path)?;
+        let env_path = env_output_uri!(&config).expect("Failed to construct ENV output path");
+        fs::remove_file(env_path)?;
+
+        Ok(())
+    });
+
+    safe_test!(test_undo_redo_and_history, {
+        let test_id = Uuid::new_v4().to_string();
+        let mut config = create_test_config(&test_id);
+        config.app[0].secret = true;
+        let secret_key = config.app[0].key.clone();
+
+        // Change a secret, add and delete settings, undo twice, redo once, then show history
+        let mut input = Cursor::new(format!(
+            "{}\nhunter2\nn\nextra\nExtra\nx\n\nn\nd\n7\ny\nu\nu\nr\nh\nq\nd\n",
+            secret_key
+        ));
+        let mut output = Cursor::new(Vec::new());
+        collect_user_input(&mut config, &mut input, &mut output)?;
+
+        // The delete was undone; the add was undone and then redone
+        let keys: Vec<&str> = config.app.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[2], "extra");
+        assert_eq!(config.app[0].value, "hunter2");
+
+        let output_str = String::from_utf8(output.into_inner())?;
+        assert!(output_str.contains("Undid: delete"));
+        assert!(output_str.contains("Redid: add extra = 'x'"));
+        assert!(output_str.contains("Session history:"));
+        assert!(output_str.contains(&format!("set {}: '********' -> '********'", secret_key)));
+        assert!(!output_str.contains("hunter2"));
+
+        Ok(())
+    });
+
     safe_test!(test_storage_type_update, {
         let test_id = Uuid::new_v4().to_string();
 
@@ -882,6 +1529,43 @@ mod tests {
         Ok(())
     });
 
+    safe_test!(test_repo_storage, {
+        let (mut config, temp_dir) = create_test_input_file!("repo");
+        config.rpcfg[0].value = "repo".to_string();
+        fs::write(temp_dir.path().join(".gitignore"), "target")?;
+
+        save_configuration(&config, false)?;
+        save_configuration(&config, false)?;
+
+        let json_path = json_output_uri!(&config).unwrap();
+        let expected_dir = temp_dir.path().join(".rpcfg");
+        assert!(Path::new(&json_path).starts_with(&expected_dir));
+        assert!(Path::new(&json_path).exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".gitignore"))?,
+            "target\n/.rpcfg/\n"
+        );
+
+        // A custom directory must stay inside the input file's directory
+        let repo_dir = ConfigItem {
+            key: "repo_dir".to_string(),
+            value: "../outside".to_string(),
+            ..Default::default()
+        };
+        assert!(repo_dir.validate_value(&repo_dir.value).is_err());
+        config.rpcfg.push(repo_dir.clone());
+        let err = save_configuration(&config, false).unwrap_err();
+        assert!(err.to_string().contains("Invalid repo_dir '../outside'"));
+        assert!(!temp_dir.path().join("outside").exists());
+        assert!(!fs::read_to_string(temp_dir.path().join(".gitignore"))?.contains(".."));
+        config.rpcfg.last_mut().unwrap().value = "config/out".to_string();
+        save_configuration(&config, false)?;
+        assert!(temp_dir.path().join("config").join("out").exists());
+        assert!(fs::read_to_string(temp_dir.path().join(".gitignore"))?.ends_with("/config/out/\n"));
+
+        Ok(())
+    });
+
     safe_test!(test_ignore_timestamps_flag, {
         let test_id = Uuid::new_v4().to_string();
         let temp_dir = tempfile::TempDir::new()?;
@@ -900,6 +1584,7 @@ mod tests {
             &mut config,
             input_path.to_str().unwrap(),
             false,
+            false,
             &mut input,
             &mut output,
         )?;
@@ -922,6 +1607,7 @@ mod tests {
             &mut config,
             input_path.to_str().unwrap(),
             false,
+            false,
             &mut input,
             &mut output,
         )?;
@@ -938,6 +1624,7 @@ mod tests {
             &mut config,
             input_path.to_str().unwrap(),
             true,
+            false,
             &mut input,
             &mut output,
         )?;
@@ -0,0 +1,239 @@
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use tracing::{debug, info};
+
+use crate::commands::collect::save_configuration;
+use crate::models::{CommandResult, Config, ValueSource};
+use crate::resolve::load_saved_values;
+use crate::{env_output_uri, files, json_output_uri, Success};
+
+/// What to leave out when copying values.
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Leave out the values of items marked as secret.
+    pub exclude_secrets: bool,
+    /// Keys whose values are left out.
+    pub exclude: Vec<String>,
+}
+
+/// Copies the saved values of one configuration into another and saves it.
+///
+/// `source` and `target` are the same schema resolved for different environments, config
+/// names or projects. Every app item of the target takes the source's saved value unless it
+/// is excluded; the rpcfg items keep the target's values so the output goes to the target.
+/// The target's schema may differ from the one the source was saved with, so every value,
+/// copied or overridden, is checked against the target's items before anything is saved.
+///
+/// When `collect` is true the user is then asked for the values that should differ between
+/// environments: the items with per-environment `defaults`, and the items that were left out.
+///
+/// # Arguments
+///
+/// * `source` - The configuration to copy from.
+/// * `target` - The configuration to copy to.
+/// * `options` - The values to leave out.
+/// * `collect` - Prompt for the environment-specific values after copying.
+/// * `input` - A mutable reference to a BufRead trait object for reading values.
+/// * `output` - A mutable reference to a Write trait object for writing prompts and messages.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The source and target are the same configuration, or the source has no saved values.
+/// * A copied or overridden value is not valid for the target's item.
+/// * The target cannot be saved.
+pub fn execute<R: BufRead, W: Write>(
+    source: &Config,
+    target: &mut Config,
+    options: &CopyOptions,
+    collect: bool,
+    input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let source_path = json_output_uri!(source)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get JSON output path"))?;
+    let target_path = json_output_uri!(target)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get JSON output path"))?;
+    if source_path == target_path {
+        anyhow::bail!("The source and target are the same configuration: {}", source_path);
+    }
+
+    let values = load_saved_values(source)?;
+    if values.is_empty() {
+        anyhow::bail!("No saved values to copy at {}; run collect first", source_path);
+    }
+
+    let mut copied = 0;
+    let mut skipped = Vec::new();
+    for item in target.app.iter_mut() {
+        let Some(value) = values.get(&item.key) else {
+            continue;
+        };
+        if options.exclude.contains(&item.key) || (options.exclude_secrets && item.secret) {
+            debug!("Leaving out {}", item.key);
+            skipped.push(item.key.clone());
+            continue;
+        }
+        item.value = value.clone();
+        item.source = ValueSource::Saved;
+        copied += 1;
+    }
+
+    let rejected: Vec<String> = target
+        .rpcfg
+        .iter()
+        .chain(target.app.iter())
+        .filter_map(|item| {
+            let e = item.validate_masked(&item.value).err()?;
+            Some(format!("{}: {}", item.key, e))
+        })
+        .collect();
+    if !rejected.is_empty() {
+        anyhow::bail!(
+            "Nothing was copied to {}; these values are not valid there:\n  {}",
+            target_path,
+            rejected.join("\n  ")
+        );
+    }
+
+    let _lock = files::lock_project(target)?;
+    save_configuration(target, false)?;
+    writeln!(output, "Copied {} values from {} to {}.", copied, source_path, target_path)?;
+    if !skipped.is_empty() {
+        writeln!(output, "Left out: {}", skipped.join(", "))?;
+    }
+    info!("Copied {} values from {} to {}", copied, source_path, target_path);
+
+    if collect {
+        let keys: Vec<String> = target
+            .app
+            .iter()
+            .filter(|item| !item.defaults.is_empty() || skipped.contains(&item.key))
+            .map(|item| item.key.clone())
+            .collect();
+        if prompt_values(target, &keys, input, output)? {
+            save_configuration(target, false)?;
+            writeln!(output, "Configuration saved.")?;
+        }
+    }
+
+    let mut result = Success!("Copied {} values to {}.", copied, target_path);
+    result.env_file = env_output_uri!(target);
+    result.json_file = Some(target_path);
+    Ok(result)
+}
+
+/// Asks for a new value for each of the keys; an empty answer keeps the current value.
+///
+/// # Returns
+///
+/// * `Result<bool>` - True if any value changed.
+fn prompt_values<R: BufRead, W: Write>(
+    config: &mut Config,
+    keys: &[String],
+    input: &mut R,
+    output: &mut W,
+) -> Result<bool> {
+    if keys.is_empty() {
+        return Ok(false);
+    }
+    writeln!(output, "Enter the environment-specific values (press Enter to keep the current one):")?;
+
+    let mut changed = false;
+    for key in keys {
+        let Some(item) = config.app.iter_mut().find(|item| &item.key == key) else {
+            continue;
+        };
+        loop {
+            write!(
+                output,
+                "{} [{}]: ",
+                item.description,
+                item.masked(&item.value)
+            )?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(changed);
+            }
+            let value = line.trim();
+            if value.is_empty() {
+                break;
+            }
+            match item.validate_masked(value) {
+                Ok(()) => {
+                    item.value = value.to_string();
+                    item.source = ValueSource::Collected;
+                    changed = true;
+                    break;
+                }
+                Err(e) => writeln!(output, "Invalid value: {}", e)?,
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ItemType;
+    use crate::{create_test_input_file, safe_test};
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+
+    safe_test!(test_copy_between_environments, {
+        let (mut source, _temp_dir) = create_test_input_file!("copy");
+        source.rpcfg[4].value = "development".to_string();
+        source.app[0].value = "shared".to_string();
+        source.app[0].defaults = BTreeMap::from([("staging".to_string(), "stg".to_string())]);
+        source.app[1].value = "dev-password".to_string();
+        source.app[1].secret = true;
+        save_configuration(&source, false)?;
+
+        let mut target = source.clone();
+        target.rpcfg[4].value = "staging".to_string();
+        target.app[1].value = String::new();
+
+        let options = CopyOptions {
+            exclude_secrets: true,
+            exclude: Vec::new(),
+        };
+        let mut output = Vec::new();
+        execute(
+            &source,
+            &mut target,
+            &options,
+            true,
+            &mut Cursor::new("stg-value\nstaging-password\n"),
+            &mut output,
+        )?;
+        let text = String::from_utf8(output)?;
+        assert!(text.contains("Copied 1 values"));
+        assert!(text.contains(&format!("Left out: {}", source.app[1].key)));
+
+        let saved = load_saved_values(&target)?;
+        assert_eq!(saved[&source.app[0].key], "stg-value");
+        assert_eq!(saved[&source.app[1].key], "staging-password");
+        assert_eq!(saved["environment"], "staging");
+        assert_eq!(load_saved_values(&source)?[&source.app[1].key], "dev-password");
+
+        // A value that does not fit the target's type is refused, and nothing is saved
+        let mut retyped = target.clone();
+        retyped.app[0].item_type = ItemType::Integer;
+        let err = execute(&source, &mut retyped, &options, false, &mut Cursor::new(""), &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("'shared' is not a valid integer"));
+        assert_eq!(load_saved_values(&target)?[&source.app[0].key], "stg-value");
+
+        // Copying onto itself is refused
+        let mut same = source.clone();
+        assert!(execute(&source, &mut same, &options, false, &mut Cursor::new(""), &mut Vec::new()).is_err());
+
+        Ok(())
+    });
+}
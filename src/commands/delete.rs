@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use std::fs;
 use std::io::{BufRead, Write};
 use tracing::{debug, info};
@@ -33,6 +33,7 @@ pub fn execute<R: BufRead, W: Write>(
         }
     }
 
+    let _lock = crate::files::lock_project(config)?;
     let mut deleted_files = Vec::new();
 
     if fs::remove_file(&json_path).is_ok() {
@@ -80,7 +81,7 @@ mod tests {
         // Step 1: Initialize a new configuration file
         {
             let mut output = Cursor::new(Vec::new());
-            init::execute(input_path.to_str().unwrap(), &mut Cursor::new(Vec::new()), &mut output)?;
+            init::execute(input_path.to_str().unwrap(), &Default::default(), &mut Cursor::new(Vec::new()), &mut output)?;
             assert!(input_path.exists(), "Input file should be created");
         }
 
@@ -91,7 +92,7 @@ mod tests {
         {
             let mut input = Cursor::new("s\nq\n"); // Save and quit
             let mut output = Cursor::new(Vec::new());
-            collect::execute(&mut config, input_path.to_str().unwrap(), false, &mut input, &mut output)?;
+            collect::execute(&mut config, input_path.to_str().unwrap(), false, false, &mut input, &mut output)?;
 
             let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
             let env_path = env_output_uri!(&config).expect("Failed to construct ENV output path");
@@ -119,15 +120,15 @@ mod tests {
             let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
             let env_path = env_output_uri!(&config).expect("Failed to construct ENV output path");
 
-            assert!(!fs::metadata(&json_path).is_ok(), "JSON file should be deleted");
-            assert!(!fs::metadata(&env_path).is_ok(), "ENV file should be deleted");
+            assert!(fs::metadata(&json_path).is_err(), "JSON file should be deleted");
+            assert!(fs::metadata(&env_path).is_err(), "ENV file should be deleted");
         }
 
         // Step 6: Recreate the output files
         {
             let mut input = Cursor::new("s\nq\n"); // Save and quit
             let mut output = Cursor::new(Vec::new());
-            collect::execute(&mut config, input_path.to_str().unwrap(), false, &mut input, &mut output)?;
+            collect::execute(&mut config, input_path.to_str().unwrap(), false, false, &mut input, &mut output)?;
 
             let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
             let env_path = env_output_uri!(&config).expect("Failed to construct ENV output path");
@@ -174,8 +175,8 @@ mod tests {
             let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
             let env_path = env_output_uri!(&config).expect("Failed to construct ENV output path");
 
-            assert!(!fs::metadata(&json_path).is_ok(), "JSON file should be deleted");
-            assert!(!fs::metadata(&env_path).is_ok(), "ENV file should be deleted");
+            assert!(fs::metadata(&json_path).is_err(), "JSON file should be deleted");
+            assert!(fs::metadata(&env_path).is_err(), "ENV file should be deleted");
         }
 
         Ok(())
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, Write};
+use tracing::info;
+
+use crate::commands::fetch;
+use crate::infer::looks_secret;
+use crate::models::{diff_values, mask_value, CommandResult, Config};
+use crate::{Fail, Success};
+
+/// One side of a comparison: a set of saved values and where they came from.
+#[derive(Debug, Clone)]
+pub struct Side {
+    pub label: String,
+    pub path: String,
+    pub values: HashMap<String, String>,
+}
+
+impl Side {
+    /// Loads the saved values of a resolved configuration.
+    ///
+    /// Environment variables and `--set` overrides are not applied, so the comparison is
+    /// between what was saved. The rpcfg items are left out: they name the output rather than
+    /// configure the app, and `environment` would always differ between environments.
+    pub fn from_config(config: &Config) -> Result<Side> {
+        let (path, mut values) = fetch::load_saved(config)?;
+        for item in &config.rpcfg {
+            values.remove(&item.key);
+        }
+        let label = config
+            .get_settings("environment")
+            .first()
+            .map(|item| item.value.clone())
+            .unwrap_or_else(|| path.clone());
+        Ok(Side {
+            label,
+            path,
+            values,
+        })
+    }
+
+    /// Loads the values of a saved JSON output file.
+    pub fn from_file(path: &str) -> Result<Side> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read JSON file: {}", path))?;
+        let values = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON from file: {}", path))?;
+        Ok(Side {
+            label: path.to_string(),
+            path: path.to_string(),
+            values,
+        })
+    }
+}
+
+/// Prints the keys that were added, removed or changed from `left` to `right`.
+///
+/// Values of items marked as secret in `schema` are masked. Keys the schema does not know,
+/// or every key without a schema, are masked when their name looks secret
+/// (see `infer::looks_secret`).
+///
+/// # Arguments
+///
+/// * `left` - The values to compare from.
+/// * `right` - The values to compare to.
+/// * `schema` - The configuration whose `secret` flags decide what to mask, if any.
+/// * `json` - Write the differences as JSON instead of text.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the differences.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult. The status is `Error` if the sides differ.
+///
+/// # Errors
+///
+/// This function will return an error if writing to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    left: &Side,
+    right: &Side,
+    schema: Option<&Config>,
+    json: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let changes = diff_values(&left.values, &right.values);
+    let is_secret = |key: &str| match schema.map(|schema| schema.get_settings(key)) {
+        Some(items) if !items.is_empty() => items.iter().any(|item| item.secret),
+        _ => looks_secret(key),
+    };
+
+    if json {
+        let changes: Vec<serde_json::Value> = changes
+            .iter()
+            .map(|change| {
+                let secret = is_secret(&change.key);
+                json!({
+                    "key": change.key,
+                    "before": change.before.as_deref().map(|value| mask_value(value, secret)),
+                    "after": change.after.as_deref().map(|value| mask_value(value, secret)),
+                })
+            })
+            .collect();
+        let report = json!({
+            "left": { "label": left.label, "path": left.path },
+            "right": { "label": right.label, "path": right.path },
+            "changes": changes,
+        });
+        writeln!(output, "{}", serde_json::to_string_pretty(&report)?)?;
+    } else {
+        writeln!(output, "--- {} ({})", left.label, left.path)?;
+        writeln!(output, "+++ {} ({})", right.label, right.path)?;
+        if changes.is_empty() {
+            writeln!(output, "No differences.")?;
+        }
+        for change in &changes {
+            writeln!(output, "{}", change.describe(is_secret(&change.key)))?;
+        }
+    }
+
+    info!("Compared {} and {}", left.path, right.path);
+
+    if changes.is_empty() {
+        Ok(Success!("No differences."))
+    } else {
+        Ok(Fail!("{} difference(s).", changes.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collect::save_configuration;
+    use crate::models::Status;
+    use crate::{create_test_input_file, safe_test};
+    use std::io::Cursor;
+
+    safe_test!(test_diff_environments, {
+        let (mut config, _temp_dir) = create_test_input_file!("diff");
+        let plain = config.app[0].key.clone();
+        let secret = config.app[1].key.clone();
+        config.app[1].secret = true;
+
+        config.rpcfg[4].value = "staging".to_string();
+        config.app[1].value = "staging-password".to_string();
+        save_configuration(&config, false)?;
+        let staging = Side::from_config(&config)?;
+
+        config.rpcfg[4].value = "production".to_string();
+        config.app[0].value = "changed".to_string();
+        config.app[1].value = "production-password".to_string();
+        save_configuration(&config, false)?;
+        let production = Side::from_config(&config)?;
+
+        let mut output = Vec::new();
+        let result = execute(&staging, &production, Some(&config), false, &mut Cursor::new(""), &mut output)?;
+        let text = String::from_utf8(output)?;
+        assert!(matches!(result.status, Status::Error));
+        assert!(text.starts_with("--- staging"));
+        assert!(text.contains(&format!("~ {}: 'initial_value1' -> 'changed'", plain)));
+        assert!(text.contains(&format!("~ {}: '********' -> '********'", secret)));
+        assert!(!text.contains("password"));
+        assert!(!text.contains("environment"));
+
+        let mut output = Vec::new();
+        execute(&staging, &production, Some(&config), true, &mut Cursor::new(""), &mut output)?;
+        let report: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(report["changes"].as_array().unwrap().len(), 2);
+
+        let result = execute(&staging, &staging, None, false, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(matches!(result.status, Status::Ok));
+
+        // Overrides do not hide a difference between the saved values
+        config.app[0].value = "initial_value1".to_string();
+        config.app[0].source = crate::models::ValueSource::Cli;
+        let overridden = Side::from_config(&config)?;
+        assert_eq!(overridden.values[&plain], "changed");
+
+        // Without a schema, keys that look secret are masked
+        let left = Side {
+            values: HashMap::from([("api_token".to_string(), "abc".to_string())]),
+            ..staging.clone()
+        };
+        let right = Side {
+            values: HashMap::from([("api_token".to_string(), "xyz".to_string())]),
+            ..staging.clone()
+        };
+        let mut output = Vec::new();
+        execute(&left, &right, None, false, &mut Cursor::new(""), &mut output)?;
+        let text = String::from_utf8(output)?;
+        assert!(text.contains("~ api_token: '********' -> '********'"));
+
+        Ok(())
+    });
+}
@@ -0,0 +1,562 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::files::{check_pair, is_gitignored, loose_permissions, make_private};
+use crate::models::{CommandResult, Config};
+use crate::resolve::resolve_values;
+use crate::rp_macros::{base_output_dir, output_root, repo_dir, uses_legacy_root};
+use crate::{env_output_uri, json_output_uri, snapshots, Fail, Success};
+
+/// The outcome of a single doctor check.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckLevel {
+    Ok,
+    Warn,
+    Error,
+}
+
+impl std::fmt::Display for CheckLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CheckLevel::Ok => "ok",
+            CheckLevel::Warn => "warn",
+            CheckLevel::Error => "error",
+        };
+        f.pad(name)
+    }
+}
+
+/// A finding reported by `doctor`.
+#[derive(Serialize, Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub level: CheckLevel,
+    pub message: String,
+}
+
+/// An output file and its state on disk.
+#[derive(Serialize, Debug, Clone)]
+pub struct OutputFile {
+    pub kind: String,
+    pub path: String,
+    pub exists: bool,
+    /// True if the file is older than the input file.
+    pub stale: bool,
+    /// The permission bits as an octal string, on Unix.
+    pub mode: Option<String>,
+}
+
+/// Everything `doctor` found out about a configuration.
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub input_file: String,
+    pub root: String,
+    pub stored: String,
+    pub outputs: Vec<OutputFile>,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    fn check(&mut self, name: &str, level: CheckLevel, message: String) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            level,
+            message,
+        });
+    }
+
+    /// Returns the number of checks that did not pass.
+    pub fn problems(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.level != CheckLevel::Ok)
+            .count()
+    }
+}
+
+/// Loads the input file for `doctor`: like the other commands, but without requiring the
+/// storage root, which `doctor` checks itself.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be loaded or its values resolved;
+/// pass it to `execute` to report it as a failed check.
+pub fn load(input_file: &str, overrides: &[(String, String)], root: Option<&Path>) -> Result<Config> {
+    let mut config = crate::parse_config_file(input_file)?;
+    config.root = root.map(Path::to_path_buf);
+    resolve_values(&mut config, overrides)?;
+    Ok(config)
+}
+
+/// Reports where rpcfg reads and writes a configuration and checks its setup.
+///
+/// The report shows the rpcfg root, the storage backend and the output paths, and checks:
+/// * `root` - the storage root can be found and is not the legacy `~/.rpcfg`.
+/// * `schema` - the input file can be loaded, and every item has a unique key and a valid value.
+///   When it cannot be loaded, this is the only check.
+/// * `staleness` - the outputs are not older than the input file.
+/// * `pair` - the JSON and ENV outputs were written by the same save.
+/// * `permissions` - files and directories holding values are accessible by their owner only.
+///   Looser permissions are tightened when `fix` is true, except on the output root, which may
+///   be a directory rpcfg did not create and is only reported.
+/// * `git` - no output lives inside a git working tree, where it could be committed. With
+///   `stored: "repo"` the output directory must be listed in `.gitignore` instead.
+///
+/// # Arguments
+///
+/// * `input_file` - The path of the input file, for the report.
+/// * `config` - The configuration to check, or the error loading it (see `load`).
+/// * `fix` - Tighten loose permissions instead of only reporting them.
+/// * `json` - Write the report as JSON instead of text.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the report.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult. The status is `Error` if any check did not pass.
+///
+/// # Errors
+///
+/// This function will return an error if a path cannot be inspected or fixed, or writing
+/// to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    input_file: &str,
+    config: Result<Config>,
+    fix: bool,
+    json: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let report = match config {
+        Ok(config) => diagnose(&config, fix)?,
+        Err(e) => {
+            let mut report = Report {
+                input_file: input_file.to_string(),
+                root: String::new(),
+                stored: String::new(),
+                outputs: Vec::new(),
+                checks: Vec::new(),
+            };
+            report.check("schema", CheckLevel::Error, format!("{:#}", e));
+            report
+        }
+    };
+
+    if json {
+        writeln!(output, "{}", serde_json::to_string_pretty(&report)?)?;
+    } else {
+        write_report(&report, output)?;
+    }
+
+    match report.problems() {
+        0 => Ok(Success!("No problems found.")),
+        problems => Ok(Fail!("{} problem(s) found.", problems)),
+    }
+}
+
+/// Runs every check and collects the results.
+pub fn diagnose(config: &Config, fix: bool) -> Result<Report> {
+    let stored = config
+        .get_settings("stored")
+        .first()
+        .map(|item| item.value.clone())
+        .unwrap_or_else(|| "local".to_string());
+    let root = output_root(config);
+    let mut report = Report {
+        input_file: config.input_file.clone(),
+        root: root
+            .as_ref()
+            .map(|root| root.display().to_string())
+            .unwrap_or_default(),
+        stored: stored.clone(),
+        outputs: Vec::new(),
+        checks: Vec::new(),
+    };
+
+    match root {
+        Err(e) => report.check("root", CheckLevel::Error, e.to_string()),
+        Ok(root) if uses_legacy_root(config) => report.check(
+            "root",
+            CheckLevel::Warn,
+            format!("{} is the legacy location; run `rpcfg relocate` to move it", root.display()),
+        ),
+        Ok(_) => {}
+    }
+
+    if base_output_dir(config).is_none() {
+        report.check(
+            "storage",
+            CheckLevel::Error,
+            format!("storage type '{}' is not supported", stored),
+        );
+    }
+
+    check_schema(config, &mut report);
+    check_outputs(config, &mut report)?;
+    check_permissions(config, fix, &mut report)?;
+    check_git(config, &mut report);
+
+    Ok(report)
+}
+
+fn check_schema(config: &Config, report: &mut Report) {
+    let mut seen = HashSet::new();
+    let mut errors = 0;
+    for item in config.rpcfg.iter().chain(config.app.iter()) {
+        if item.key.trim().is_empty() {
+            errors += 1;
+            report.check("schema", CheckLevel::Error, "an item has an empty key".to_string());
+        } else if !seen.insert(item.key.as_str()) {
+            errors += 1;
+            report.check(
+                "schema",
+                CheckLevel::Error,
+                format!("key '{}' is defined more than once", item.key),
+            );
+        }
+        if let Err(e) = item.validate_value(&item.value) {
+            errors += 1;
+            report.check("schema", CheckLevel::Error, format!("{}: {}", item.key, e));
+        }
+    }
+    if errors == 0 {
+        report.check(
+            "schema",
+            CheckLevel::Ok,
+            format!("{} items are valid", config.rpcfg.len() + config.app.len()),
+        );
+    }
+}
+
+fn check_outputs(config: &Config, report: &mut Report) -> Result<()> {
+    let input_modified = fs::metadata(&config.input_file)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let outputs = [
+        ("json", json_output_uri!(config)),
+        ("env", env_output_uri!(config)),
+    ];
+    for (kind, path) in outputs {
+        let Some(path) = path else { continue };
+        let metadata = fs::metadata(&path).ok();
+        let stale = match (&metadata, input_modified) {
+            (Some(metadata), Some(input_modified)) => metadata.modified()? < input_modified,
+            _ => false,
+        };
+        let mode = match &metadata {
+            Some(metadata) => file_mode(metadata),
+            None => None,
+        };
+        if metadata.is_none() {
+            report.check(
+                "staleness",
+                CheckLevel::Warn,
+                format!("{} does not exist; run collect", path),
+            );
+        } else if stale {
+            report.check(
+                "staleness",
+                CheckLevel::Warn,
+                format!("{} is older than the input file; run collect", path),
+            );
+        }
+        report.outputs.push(OutputFile {
+            kind: kind.to_string(),
+            path,
+            exists: metadata.is_some(),
+            stale,
+            mode,
+        });
+    }
+    if report.outputs.iter().all(|output| output.exists && !output.stale) {
+        report.check("staleness", CheckLevel::Ok, "outputs are up to date".to_string());
+    }
+    if let (Some(json_path), Some(env_path)) = (json_output_uri!(config), env_output_uri!(config)) {
+        match check_pair(Path::new(&json_path), Path::new(&env_path)) {
+            Ok(()) => report.check("pair", CheckLevel::Ok, "outputs are from the same save".to_string()),
+            Err(e) => report.check("pair", CheckLevel::Error, e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(format!("{:04o}", metadata.permissions().mode() & 0o777))
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<String> {
+    None
+}
+
+fn check_permissions(config: &Config, fix: bool, report: &mut Report) -> Result<()> {
+    if !cfg!(unix) {
+        report.check(
+            "permissions",
+            CheckLevel::Ok,
+            "permission checks are only done on Unix".to_string(),
+        );
+        return Ok(());
+    }
+
+    let mut problems = 0;
+    for (path, owned) in private_paths(config)? {
+        match loose_permissions(&path)? {
+            None => debug!("{} is private", path.display()),
+            // The root may be a directory the user passed with --root, so it is never changed
+            Some(mode) if !owned => {
+                problems += 1;
+                report.check(
+                    "permissions",
+                    CheckLevel::Warn,
+                    format!(
+                        "{} is {:04o}, should be accessible by its owner only; rpcfg does not change it, restrict it yourself",
+                        path.display(),
+                        mode
+                    ),
+                );
+            }
+            Some(mode) if fix => {
+                make_private(&path)?;
+                info!("Restricted permissions on {}", path.display());
+                report.check(
+                    "permissions",
+                    CheckLevel::Ok,
+                    format!("fixed {} (was {:04o})", path.display(), mode),
+                );
+            }
+            Some(mode) => {
+                problems += 1;
+                report.check(
+                    "permissions",
+                    CheckLevel::Warn,
+                    format!(
+                        "{} is {:04o}, should be accessible by its owner only; run with --fix",
+                        path.display(),
+                        mode
+                    ),
+                );
+            }
+        }
+    }
+    if problems == 0 && !fix {
+        report.check(
+            "permissions",
+            CheckLevel::Ok,
+            "files and directories are private".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Lists the existing directories and files that should be private to their owner, with
+/// true for the ones rpcfg created and may tighten. The output root is listed with false.
+fn private_paths(config: &Config) -> Result<Vec<(PathBuf, bool)>> {
+    let root: Vec<PathBuf> = output_root(config).into_iter().collect();
+    let mut paths = Vec::new();
+    if let Some(base_dir) = base_output_dir(config) {
+        if let Some(project_dir) = base_dir.parent() {
+            paths.push(project_dir.to_path_buf());
+        }
+        paths.push(base_dir);
+    }
+    paths.extend(json_output_uri!(config).map(PathBuf::from));
+    paths.extend(env_output_uri!(config).map(PathBuf::from));
+    if let Some(history_dir) = snapshots::history_dir(config) {
+        if let Some(parent) = history_dir.parent() {
+            paths.push(parent.to_path_buf());
+        }
+        paths.push(history_dir);
+        for snapshot in snapshots::list(config)? {
+            paths.push(snapshot.json_path);
+            paths.push(snapshot.env_path);
+        }
+    }
+    paths.retain(|path| !root.contains(path));
+    let paths = root
+        .into_iter()
+        .map(|path| (path, false))
+        .chain(paths.into_iter().map(|path| (path, true)))
+        .filter(|(path, _)| path.exists())
+        .collect();
+    Ok(paths)
+}
+
+fn check_git(config: &Config, report: &mut Report) {
+    if report.stored == "repo" {
+        // Repo storage lives in the working tree on purpose; it only has to be ignored
+        match repo_dir(config) {
+            Err(e) => report.check("git", CheckLevel::Error, e.to_string()),
+            Ok(_) if is_gitignored(config) => {
+                report.check("git", CheckLevel::Ok, "the repo output directory is in .gitignore".to_string());
+            }
+            Ok(repo_dir) => report.check(
+                "git",
+                CheckLevel::Warn,
+                format!("/{}/ is not in .gitignore next to the input file; run collect to add it", repo_dir),
+            ),
+        }
+        return;
+    }
+
+    let mut inside = Vec::new();
+    for output in &report.outputs {
+        if let Some(work_tree) = git_work_tree(Path::new(&output.path)) {
+            inside.push(format!(
+                "{} is inside the git working tree {}; it could be committed",
+                output.path,
+                work_tree.display()
+            ));
+        }
+    }
+    if inside.is_empty() {
+        report.check("git", CheckLevel::Ok, "no output is inside a git working tree".to_string());
+    }
+    for message in inside {
+        report.check("git", CheckLevel::Warn, message);
+    }
+}
+
+/// Returns the root of the git working tree containing a path, if any.
+///
+/// The path itself does not need to exist; its closest existing ancestor is used.
+pub fn git_work_tree(path: &Path) -> Option<PathBuf> {
+    let mut dir = path.parent();
+    while let Some(candidate) = dir {
+        if candidate.join(".git").exists() {
+            return Some(candidate.to_path_buf());
+        }
+        dir = candidate.parent();
+    }
+    None
+}
+
+fn write_report<W: Write>(report: &Report, output: &mut W) -> Result<()> {
+    writeln!(output, "Input file:  {}", report.input_file)?;
+    writeln!(output, "Root:        {}", report.root)?;
+    writeln!(output, "Storage:     {}", report.stored)?;
+    for file in &report.outputs {
+        let mut state = vec![if file.exists { "exists" } else { "missing" }.to_string()];
+        if file.stale {
+            state.push("stale".to_string());
+        }
+        if let Some(mode) = &file.mode {
+            state.push(mode.clone());
+        }
+        writeln!(
+            output,
+            "{:<12} {} ({})",
+            format!("{} output:", file.kind.to_uppercase()),
+            file.path,
+            state.join(", ")
+        )?;
+    }
+
+    writeln!(output)?;
+    writeln!(output, "Checks:")?;
+    for check in &report.checks {
+        writeln!(output, "  {:<5} {}: {}", check.level, check.name, check.message)?;
+    }
+
+    writeln!(output)?;
+    match report.problems() {
+        0 => writeln!(output, "No problems found.")?,
+        problems => writeln!(output, "{} problem(s) found.", problems)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collect::save_configuration;
+    use crate::models::Status;
+    use crate::{create_test_input_file, safe_test};
+    use std::io::Cursor;
+
+    #[cfg(unix)]
+    safe_test!(test_doctor_permissions, {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &str| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let (config, temp_dir) = create_test_input_file!("doctor");
+        save_configuration(&config, false)?;
+        let json_path = json_output_uri!(&config).unwrap();
+        let env_path = env_output_uri!(&config).unwrap();
+        assert_eq!(mode(&json_path), 0o600);
+        assert_eq!(mode(&env_path), 0o600);
+
+        fs::set_permissions(&json_path, fs::Permissions::from_mode(0o644))?;
+        let mut output = Vec::new();
+        let result = execute(&config.input_file, Ok(config.clone()), false, false, &mut Cursor::new(""), &mut output)?;
+        let report = String::from_utf8(output)?;
+        assert!(matches!(result.status, Status::Error));
+        assert!(report.contains(&format!("{} is 0644", json_path)));
+
+        let result = execute(&config.input_file, Ok(config.clone()), true, false, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(matches!(result.status, Status::Ok));
+        assert_eq!(mode(&json_path), 0o600);
+
+        // A root passed with --root is reported but never changed
+        let mut config = config;
+        let root = temp_dir.path().join("shared");
+        fs::create_dir(&root)?;
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o755))?;
+        config.root = Some(root.clone());
+        save_configuration(&config, false)?;
+        let mut output = Vec::new();
+        let result = execute(&config.input_file, Ok(config.clone()), true, false, &mut Cursor::new(""), &mut output)?;
+        assert!(matches!(result.status, Status::Error));
+        assert!(String::from_utf8(output)?.contains("rpcfg does not change it"));
+        assert_eq!(mode(root.to_str().unwrap()), 0o755);
+
+        Ok(())
+    });
+
+    safe_test!(test_doctor_json_report, {
+        let (mut config, temp_dir) = create_test_input_file!("doctor-json");
+        config.app[0].key = config.app[1].key.clone();
+
+        let mut output = Vec::new();
+        let result = execute(&config.input_file, Ok(config.clone()), false, true, &mut Cursor::new(""), &mut output)?;
+        assert!(matches!(result.status, Status::Error));
+        let report: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(report["stored"], "local");
+        assert_eq!(report["outputs"][0]["exists"], false);
+        let checks = report["checks"].as_array().unwrap();
+        assert!(checks
+            .iter()
+            .any(|check| check["name"] == "schema" && check["level"] == "error"));
+        assert!(checks
+            .iter()
+            .any(|check| check["name"] == "staleness" && check["level"] == "warn"));
+
+        // A file that cannot be loaded is a failed check, not an error
+        let broken = temp_dir.path().join("broken.json");
+        let broken = broken.to_str().unwrap();
+        fs::write(broken, "{ not json")?;
+        let mut output = Vec::new();
+        let result = execute(broken, load(broken, &[], None), false, true, &mut Cursor::new(""), &mut output)?;
+        assert!(matches!(result.status, Status::Error));
+        let report: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(report["checks"][0]["name"], "schema");
+        assert!(report["checks"][0]["message"].as_str().unwrap().contains("Failed to parse JSON"));
+        let mut output = Vec::new();
+        execute(broken, load(broken, &[], None), false, false, &mut Cursor::new(""), &mut output)?;
+        assert!(String::from_utf8(output)?.contains("error schema: Failed to parse JSON"));
+
+        fs::create_dir(temp_dir.path().join(".git"))?;
+        let inside = temp_dir.path().join("out").join("config.json");
+        assert_eq!(git_work_tree(&inside), Some(temp_dir.path().to_path_buf()));
+
+        Ok(())
+    });
+}
@@ -2,14 +2,76 @@ use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, BufRead, Write};
+use std::path::Path;
 use tracing::{debug, info};
-use crate::models::{Config, CommandResult, Status};
-use crate::json_output_uri;
+use crate::models::{Config, CommandResult, Status, ValueSource};
+use crate::{env_output_uri, files, json_output_uri};
+
+/// Loads the values saved in a configuration's JSON output file, without any overrides.
+///
+/// The JSON output is only loaded if the ENV output next to it was written by the same save
+/// (see `files::check_pair`).
+///
+/// # Returns
+///
+/// * `Result<(String, HashMap<String, String>)>` - The path of the JSON output file and the values.
+///
+/// # Errors
+///
+/// This function will return an error if the JSON output file cannot be found, read or parsed,
+/// or the ENV output was written by a different save.
+pub fn load_saved(config: &Config) -> Result<(String, HashMap<String, String>)> {
+    // Get the JSON output file path
+    let json_path = json_output_uri!(config)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get JSON output path"))?;
+
+    // Trace the JSON file path to stderr
+    debug!("JSON file path: {}", json_path);
+
+    if let Some(env_path) = env_output_uri!(config) {
+        files::check_pair(Path::new(&json_path), Path::new(&env_path))?;
+    }
+
+    // Open and read the JSON file
+    let file = File::open(&json_path)
+        .with_context(|| format!("Failed to open JSON file: {}", json_path))?;
+    let reader = BufReader::new(file);
+
+    // Parse the JSON into a HashMap
+    let config_map: HashMap<String, String> = serde_json::from_reader(reader)
+        .with_context(|| format!("Failed to parse JSON from file: {}", json_path))?;
+
+    Ok((json_path, config_map))
+}
+
+/// Loads the saved values of a configuration, with environment and command line overrides applied.
+///
+/// # Returns
+///
+/// * `Result<(String, HashMap<String, String>)>` - The path of the JSON output file and the values.
+///
+/// # Errors
+///
+/// This function will return an error if the JSON output file cannot be found, read or parsed,
+/// or the ENV output was written by a different save.
+pub fn load_values(config: &Config) -> Result<(String, HashMap<String, String>)> {
+    let (json_path, mut config_map) = load_saved(config)?;
+
+    // Environment and command line overrides win over the saved values
+    for item in config.rpcfg.iter().chain(config.app.iter()) {
+        if matches!(item.source, ValueSource::Env | ValueSource::Cli) {
+            config_map.insert(item.key.clone(), item.value.clone());
+        }
+    }
+
+    Ok((json_path, config_map))
+}
 
 /// Fetches and displays the current configuration from a JSON file.
 ///
 /// This function reads the configuration from a JSON file specified in the Config object,
 /// parses it into a HashMap, and writes the prettified JSON to the provided output stream.
+/// Items whose value came from an environment variable or a `--set` flag replace the saved value.
 ///
 /// # Arguments
 ///
@@ -33,21 +95,7 @@ pub fn execute<R: BufRead, W: Write>(
     _input: &mut R,
     output: &mut W,
 ) -> Result<crate::CommandResult> {
-    // Get the JSON output file path
-    let json_path = json_output_uri!(config)
-        .ok_or_else(|| anyhow::anyhow!("Failed to get JSON output path"))?;
-
-    // Trace the JSON file path to stderr
-    debug!("JSON file path: {}", json_path);
-
-    // Open and read the JSON file
-    let file = File::open(&json_path)
-        .with_context(|| format!("Failed to open JSON file: {}", json_path))?;
-    let reader = BufReader::new(file);
-
-    // Parse the JSON into a HashMap
-    let config_map: HashMap<String, String> = serde_json::from_reader(reader)
-        .with_context(|| format!("Failed to parse JSON from file: {}", json_path))?;
+    let (json_path, config_map) = load_values(config)?;
 
     // Write the fetched configuration to the output
     let json_output = serde_json::to_string_pretty(&config_map)?;
@@ -115,7 +163,22 @@ mod tests {
             assert_eq!(json_data["new_key"], "default_value");
         }
 
-        // Step 3: Verify the new setting is saved to the input file
+        // Step 3: A save interrupted between the JSON and ENV renames is refused, and the next save repairs it
+        {
+            let mut input = Cursor::new("6\nchanged\ns\nq\n");
+            files::CRASH_AFTER_RENAMES.with(|crash| crash.set(Some(1)));
+            let result = collect::collect_user_input(&mut config, &mut input, &mut Cursor::new(Vec::new()));
+            files::CRASH_AFTER_RENAMES.with(|crash| crash.set(None));
+            assert!(result.is_err());
+
+            let err = execute(&config, &mut Cursor::new(Vec::new()), &mut Cursor::new(Vec::new())).unwrap_err();
+            assert!(err.to_string().contains("written by different saves"));
+
+            collect::collect_user_input(&mut config, &mut Cursor::new("s\nq\n"), &mut Cursor::new(Vec::new()))?;
+            execute(&config, &mut Cursor::new(Vec::new()), &mut Cursor::new(Vec::new()))?;
+        }
+
+        // Step 4: Verify the new setting is saved to the input file
         {
             let updated_config = parse_config_file(&config.input_file)?;
             let new_item = updated_config.app.iter().find(|item| item.key == "new_key");
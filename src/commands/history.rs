@@ -0,0 +1,67 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use tracing::info;
+
+use crate::models::{diff_values, CommandResult, Config};
+use crate::{snapshots, Success};
+
+/// Lists the saved snapshots of the configuration's output files, newest first.
+///
+/// Each snapshot is followed by the keys that were added, removed or changed since the
+/// snapshot before it. Values of items marked as secret are masked.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the Config object whose snapshots are listed.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the list.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * A snapshot cannot be read or parsed.
+/// * Writing to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    config: &Config,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let snapshots = snapshots::list(config)?;
+    if snapshots.is_empty() {
+        writeln!(output, "No snapshots found.")?;
+        return Ok(Success!("No snapshots found."));
+    }
+
+    let mut entries = Vec::new();
+    let mut previous: Option<HashMap<String, String>> = None;
+    for snapshot in &snapshots {
+        let values = snapshot.values()?;
+        let changes = previous
+            .as_ref()
+            .map(|previous| diff_values(previous, &values));
+        entries.push((snapshot, values.len(), changes));
+        previous = Some(values);
+    }
+
+    for (snapshot, count, changes) in entries.iter().rev() {
+        writeln!(output, "{}  ({} items)", snapshot.id, count)?;
+        match changes {
+            None => writeln!(output, "    (first snapshot)")?,
+            Some(changes) if changes.is_empty() => writeln!(output, "    (no changes)")?,
+            Some(changes) => {
+                for change in changes {
+                    writeln!(output, "    {}", change.describe(config.is_secret(&change.key)))?;
+                }
+            }
+        }
+    }
+
+    info!("Listed {} snapshots", snapshots.len());
+
+    Ok(Success!("Listed {} snapshots.", snapshots.len()))
+}
@@ -0,0 +1,186 @@
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use tracing::{debug, info};
+
+use crate::commands::collect::save_configuration;
+use crate::models::{CommandResult, Config, ConfigItem, ValueSource};
+use crate::resolve::env_var_names;
+use crate::{env_output_uri, files, json_output_uri, Success};
+
+/// How to treat variables that do not match an item.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Create a new app item for each unmatched variable.
+    pub create: bool,
+    /// List the unmatched variables. Off for the process environment, which holds many
+    /// variables unrelated to the configuration.
+    pub report_unmatched: bool,
+}
+
+/// Imports values from environment variables, such as those of a `.env` file, and saves them.
+///
+/// A variable sets the app item whose `temp_environment_variable_name`, `RPCFG_<KEY>` name
+/// or uppercased key equals its name. The rpcfg items are never imported, since they decide
+/// where the output is written. Every value is checked against its item first; if any is
+/// invalid, nothing is imported.
+///
+/// # Arguments
+///
+/// * `config` - A mutable reference to the Config object to import into.
+/// * `vars` - The variables to import, as name and value.
+/// * `source` - Where the variables came from, for messages.
+/// * `options` - How to treat unmatched variables.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the report.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error, listing every rejected key, if a value is not valid for
+/// its item, or if the configuration cannot be saved.
+pub fn execute<R: BufRead, W: Write>(
+    config: &mut Config,
+    vars: &[(String, String)],
+    source: &str,
+    options: &ImportOptions,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let mut imported = Vec::new();
+    let mut unmatched = Vec::new();
+
+    let rejected: Vec<String> = vars
+        .iter()
+        .filter_map(|(name, value)| {
+            let item = find_item(config, name)?;
+            let e = item.validate_masked(value).err()?;
+            Some(format!("{} (from {}): {}", item.key, name, e))
+        })
+        .collect();
+    if !rejected.is_empty() {
+        anyhow::bail!(
+            "Nothing was imported from {}; these values are not valid:\n  {}",
+            source,
+            rejected.join("\n  ")
+        );
+    }
+
+    for (name, value) in vars {
+        match find_item(config, name) {
+            Some(item) => {
+                debug!("{} sets {}", name, item.key);
+                item.value = value.clone();
+                item.source = ValueSource::Collected;
+                imported.push(item.key.clone());
+            }
+            None => unmatched.push((name, value)),
+        }
+    }
+
+    let mut created = Vec::new();
+    if options.create {
+        for (name, value) in &unmatched {
+            let item = new_item(name, value);
+            if config.get_settings(&item.key).is_empty() {
+                created.push(item.key.clone());
+                config.app.push(item);
+            }
+        }
+        config.schema_changed |= !created.is_empty();
+    }
+
+    let _lock = files::lock_project(config)?;
+    save_configuration(config, config.schema_changed)?;
+
+    writeln!(output, "Imported {} values from {}.", imported.len(), source)?;
+    if !imported.is_empty() {
+        writeln!(output, "  Set: {}", imported.join(", "))?;
+    }
+    if !created.is_empty() {
+        writeln!(output, "  Created: {}", created.join(", "))?;
+    } else if !unmatched.is_empty() {
+        if options.report_unmatched {
+            let names: Vec<&str> = unmatched.iter().map(|(name, _)| name.as_str()).collect();
+            writeln!(output, "  Unmatched: {}", names.join(", "))?;
+        } else {
+            writeln!(output, "  {} variables did not match an item.", unmatched.len())?;
+        }
+    }
+    info!("Imported {} values from {}", imported.len(), source);
+
+    let mut result = Success!(
+        "Imported {} values and created {} items.",
+        imported.len(),
+        created.len()
+    );
+    result.env_file = env_output_uri!(config);
+    result.json_file = json_output_uri!(config);
+    Ok(result)
+}
+
+/// Finds the app item a variable sets.
+fn find_item<'a>(config: &'a mut Config, name: &str) -> Option<&'a mut ConfigItem> {
+    config.app.iter_mut().find(|item| {
+        env_var_names(item).iter().any(|var| var == name) || item.key.to_uppercase() == name
+    })
+}
+
+/// Creates an app item for an unmatched variable (see `infer::item_from_var`).
+fn new_item(name: &str, value: &str) -> ConfigItem {
+    ConfigItem {
+        source: ValueSource::Collected,
+        ..crate::infer::item_from_var(name, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ItemType;
+    use crate::resolve::load_saved_values;
+    use crate::{create_test_input_file, dotenv, safe_test};
+    use std::fs;
+    use std::io::Cursor;
+
+    safe_test!(test_import_dotenv, {
+        let (mut config, _temp_dir) = create_test_input_file!("import");
+        config.app[0].temp_environment_variable_name = "APP_API_URL".to_string();
+        config.app[1].key = "db_name".to_string();
+        let vars = dotenv::parse("APP_API_URL=from-env-name\nDB_NAME=from-key\nNEW_SETTING=new\n")?;
+
+        let mut output = Vec::new();
+        let options = ImportOptions {
+            create: false,
+            report_unmatched: true,
+        };
+        execute(&mut config, &vars, ".env", &options, &mut Cursor::new(""), &mut output)?;
+        let report = String::from_utf8(output)?;
+        assert!(report.contains("Imported 2 values from .env."));
+        assert!(report.contains("Unmatched: NEW_SETTING"));
+        let saved = load_saved_values(&config)?;
+        assert_eq!(saved[&config.app[0].key], "from-env-name");
+        assert_eq!(saved[&config.app[1].key], "from-key");
+
+        let options = ImportOptions {
+            create: true,
+            report_unmatched: true,
+        };
+        execute(&mut config, &vars, ".env", &options, &mut Cursor::new(""), &mut Vec::new())?;
+        assert_eq!(load_saved_values(&config)?["new_setting"], "new");
+        let schema = fs::read_to_string(&config.input_file)?;
+        assert!(schema.contains("\"NEW_SETTING\""));
+
+        // An invalid value rejects the whole import
+        config.app[1].item_type = ItemType::Integer;
+        let vars = dotenv::parse("APP_API_URL=changed\nDB_NAME=many\n")?;
+        let err = execute(&mut config, &vars, ".env", &options, &mut Cursor::new(""), &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("db_name (from DB_NAME): 'many' is not a valid integer"));
+        assert_eq!(load_saved_values(&config)?[&config.app[0].key], "from-env-name");
+
+        Ok(())
+    });
+}
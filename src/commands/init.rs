@@ -1,8 +1,40 @@
-use crate::models::{Config, CommandResult, Status};
+use crate::commands::schema;
+use crate::compose::load_composed;
+use crate::files;
+use crate::infer::infer_from_file;
+use crate::migrate;
+use crate::models::{Config, ConfigItem, CommandResult, Status};
+use crate::templates::Template;
 use anyhow::{Context, Result};
-use std::fs;
 use std::io::{BufRead, Write};
-use tracing::info;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Options for generating the configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// A template whose app items the schema starts from.
+    pub template: Option<Template>,
+    /// A `.env` or JSON file to infer the app items from.
+    pub from: Option<String>,
+    /// The default for the `project_name` item.
+    pub project: Option<String>,
+    /// The default for the `environment` item.
+    pub env: Option<String>,
+    /// Overwrite an existing file.
+    pub force: bool,
+    /// Add the missing items to an existing file instead of replacing it.
+    pub merge: bool,
+    /// Ask for the project name, config name and environment.
+    pub prompt: bool,
+}
+
+/// The rpcfg items asked for when `InitOptions::prompt` is set, with their prompts.
+const PROMPTED_ITEMS: &[(&str, &str)] = &[
+    ("project_name", "Project name"),
+    ("config_name", "Config name"),
+    ("environment", "Environment"),
+];
 
 /// Initializes a new configuration file with default settings.
 ///
@@ -10,11 +42,28 @@ use tracing::info;
 /// serializes it to JSON, and writes it to the specified output path.
 /// It also writes a confirmation message to the provided output stream.
 ///
+/// The JSON Schema of the format is written next to the file (see `schema::write_next_to`)
+/// and referenced as `$schema`, so editors validate the file.
+///
+/// With `options.template`, the schema starts with the template's app items.
+/// With `options.from`, app items are inferred from an existing `.env` or JSON file
+/// (see `infer::infer_from_file`), including their types and which look secret. rpcfg values
+/// found in the file, and `options.project` and `options.env`, become the rpcfg defaults. Inferred
+/// items with the key of a template item are left out.
+/// With `options.prompt`, the project name, config name and environment not given as options
+/// are read from `input`; an empty answer keeps the default.
+///
+/// An existing file is only replaced with `options.force`. With `options.merge`, the rpcfg
+/// items it lacks (and the template and inferred app items it lacks) are added to it instead;
+/// items it inherits through `extends` or `include` are not missing. The existing defaults
+/// are kept, except those given with `options.project` and `options.env`.
+///
 /// # Arguments
 ///
 /// * `output_path` - A string slice that holds the path where the configuration file will be written.
-/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
-/// * `output` - A mutable reference to a Write trait object for writing the confirmation message.
+/// * `options` - The template and file to take items from, the rpcfg defaults to use and how to treat an existing file.
+/// * `input` - A mutable reference to a BufRead trait object for reading answers to the prompts.
+/// * `output` - A mutable reference to a Write trait object for writing prompts and the confirmation message.
 ///
 /// # Returns
 ///
@@ -23,16 +72,55 @@ use tracing::info;
 /// # Errors
 ///
 /// This function will return an error if:
+/// * The file exists and neither `force` nor `merge` is set.
+/// * The file named by `options.from`, or the existing file when merging, cannot be read or parsed.
+/// * The project or environment name is not valid.
 /// * Writing the configuration file fails.
 /// * Writing to the output stream fails.
 pub fn execute<R: BufRead, W: Write>(
     output_path: &str,
-    _input: &mut R,
+    options: &InitOptions,
+    input: &mut R,
     output: &mut W,
 ) -> Result<CommandResult> {
-    let config = Config::default();
+    let exists = Path::new(output_path).exists();
+    if exists && options.merge {
+        return merge(output_path, options, output);
+    }
+    if exists && !options.force {
+        anyhow::bail!(
+            "{} already exists; use --force to overwrite it or --merge to add the missing items",
+            output_path
+        );
+    }
+
+    let mut config = Config::default();
+    if let Some(template) = &options.template {
+        writeln!(output, "Using template {} with {} items", template.name, template.app.len())?;
+        config.app = template.app.clone();
+    }
+    if let Some(from) = &options.from {
+        let inferred = infer_from_file(from)?;
+        writeln!(output, "Inferred {} items from {}", inferred.items.len(), from)?;
+        for item in inferred.items.iter().filter(|item| item.secret) {
+            writeln!(output, "  {} looks secret; its value was left out", item.key)?;
+        }
+        for item in inferred.items {
+            if config.get_settings(&item.key).is_empty() {
+                config.app.push(item);
+            }
+        }
+        set_rpcfg_defaults(&mut config, inferred.rpcfg)?;
+    }
+    set_rpcfg_defaults(&mut config, flag_defaults(options))?;
+    if options.prompt {
+        prompt_rpcfg_defaults(&mut config, options, input, output)?;
+    }
+    config.schema = Some(schema::write_next_to(output_path)?);
+
     let json = serde_json::to_string_pretty(&config)?;
-    fs::write(output_path, &json)
+    // Replace an existing file (with --force) atomically, so an interruption cannot truncate it
+    files::write_atomic(Path::new(output_path), &json)
         .with_context(|| format!("Failed to write configuration file: {}", output_path))?;
 
     writeln!(output, "Configuration file initialized at: {}", output_path)?;
@@ -47,21 +135,161 @@ pub fn execute<R: BufRead, W: Write>(
     })
 }
 
+/// Adds the items an existing file lacks, leaving the ones it has untouched.
+///
+/// An item counts as present when the file gets it through `extends` or `include`, so the
+/// defaults of a base file are not overridden. Only the defaults given as options are changed;
+/// one the file inherits is overridden in the file itself.
+fn merge<W: Write>(output_path: &str, options: &InitOptions, output: &mut W) -> Result<CommandResult> {
+    let (mut config, upgraded) = migrate::read_upgraded(Path::new(output_path), output_path)?;
+    for change in &upgraded {
+        writeln!(output, "{}", change)?;
+    }
+    let composed = load_composed(output_path)?;
+
+    let mut candidates = Config::default().rpcfg;
+    if let Some(template) = &options.template {
+        candidates.extend(template.app.iter().cloned());
+    }
+    if let Some(from) = &options.from {
+        candidates.extend(infer_from_file(from)?.items);
+    }
+    let rpcfg_keys: Vec<String> = Config::default().rpcfg.into_iter().map(|item| item.key).collect();
+
+    let mut added = Vec::new();
+    for item in candidates {
+        if composed.get_settings(&item.key).is_empty() && config.get_settings(&item.key).is_empty() {
+            added.push(item.key.clone());
+            if rpcfg_keys.contains(&item.key) {
+                config.rpcfg.push(item);
+            } else {
+                config.app.push(item);
+            }
+        }
+    }
+    let defaults = flag_defaults(options);
+    for (key, _) in &defaults {
+        if config.get_settings(key).is_empty() {
+            if let Some(inherited) = composed.rpcfg.iter().find(|item| item.key == *key) {
+                added.push(key.clone());
+                config.rpcfg.push(ConfigItem {
+                    defined_in: String::new(),
+                    ..inherited.clone()
+                });
+            }
+        }
+    }
+    set_rpcfg_defaults(&mut config, defaults)?;
+    if config.schema.is_none() {
+        config.schema = Some(schema::write_next_to(output_path)?);
+        added.push("$schema".to_string());
+    }
+
+    let json = serde_json::to_string_pretty(&config)?;
+    files::write_atomic(Path::new(output_path), &json)
+        .with_context(|| format!("Failed to write configuration file: {}", output_path))?;
+
+    let message = if added.is_empty() {
+        format!("{} already has every item.", output_path)
+    } else {
+        format!("Added {} to {}.", added.join(", "), output_path)
+    };
+    writeln!(output, "{}", message)?;
+    info!("Merged configuration file");
+
+    Ok(CommandResult {
+        status: Status::Ok,
+        message,
+        env_file: None,
+        json_file: Some(output_path.to_string()),
+    })
+}
+
+/// The rpcfg defaults given as options.
+fn flag_defaults(options: &InitOptions) -> Vec<(String, String)> {
+    let mut defaults = Vec::new();
+    if let Some(project) = &options.project {
+        defaults.push(("project_name".to_string(), project.clone()));
+    }
+    if let Some(env) = &options.env {
+        defaults.push(("environment".to_string(), env.clone()));
+    }
+    defaults
+}
+
+fn set_rpcfg_defaults(config: &mut Config, defaults: Vec<(String, String)>) -> Result<()> {
+    for (key, value) in defaults {
+        if let Some(item) = config.rpcfg.iter_mut().find(|item| item.key == key) {
+            item.validate_value(&value)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e))?;
+            debug!("Setting the default of {} to {}", key, value);
+            item.default = value.clone();
+            item.value = value;
+        }
+    }
+    Ok(())
+}
+
+/// Asks for the rpcfg defaults that were not given as options.
+fn prompt_rpcfg_defaults<R: BufRead, W: Write>(
+    config: &mut Config,
+    options: &InitOptions,
+    input: &mut R,
+    output: &mut W,
+) -> Result<()> {
+    for (key, prompt) in PROMPTED_ITEMS {
+        let given = match *key {
+            "project_name" => options.project.is_some(),
+            "environment" => options.env.is_some(),
+            _ => false,
+        };
+        let Some(item) = config.rpcfg.iter_mut().find(|item| item.key == *key) else {
+            continue;
+        };
+        if given {
+            continue;
+        }
+        loop {
+            write!(output, "{} [{}]: ", prompt, item.default)?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let value = line.trim();
+            if value.is_empty() {
+                break;
+            }
+            match item.validate_value(value) {
+                Ok(()) => {
+                    item.default = value.to_string();
+                    item.value = value.to_string();
+                    break;
+                }
+                Err(e) => writeln!(output, "Invalid value: {}", e)?,
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use std::io::Cursor;
-    use tempfile::NamedTempFile;
+    use tempfile::TempDir;
 
     #[test]
     fn test_init_command() -> Result<()> {
-        let temp_file = NamedTempFile::new()?;
-        let output_path = temp_file.path().to_str().unwrap();
+        let temp_dir = TempDir::new()?;
+        let output_path = temp_dir.path().join("repo_config.json");
+        let output_path = output_path.to_str().unwrap();
 
         let mut input = Cursor::new(Vec::new());
         let mut output = Cursor::new(Vec::new());
 
-        let result = execute(output_path, &mut input, &mut output)?;
+        let result = execute(output_path, &InitOptions::default(), &mut input, &mut output)?;
        // assert_eq!(result.status, Status::Ok);
         assert!(result.message.contains("Configuration file initialized"));
 
@@ -77,6 +305,117 @@ mod tests {
         assert_eq!(config.rpcfg[0].key, "stored");
         assert_eq!(config.rpcfg[0].default, "local");
 
+        // Editors find the JSON Schema through `$schema`, and rpcfg still parses the file
+        assert_eq!(config.schema.as_deref(), Some("./rpcfg.schema.json"));
+        assert!(temp_dir.path().join(schema::SCHEMA_FILE_NAME).exists());
+        assert!(crate::parse_config_file(output_path).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_from_dotenv() -> Result<()> {
+        let dir = TempDir::new()?;
+        let env_path = dir.path().join(".env");
+        fs::write(&env_path, "API_URL=https://example.com\nSTRIPE_SECRET=sk_live_123\n")?;
+        let output_path = dir.path().join("repo_config.json");
+
+        let options = InitOptions {
+            from: Some(env_path.to_string_lossy().into_owned()),
+            project: Some("shop".to_string()),
+            env: Some("staging".to_string()),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        execute(output_path.to_str().unwrap(), &options, &mut Cursor::new(Vec::new()), &mut output)?;
+        assert!(String::from_utf8(output)?.contains("stripe_secret looks secret"));
+
+        let content = fs::read_to_string(&output_path)?;
+        assert!(!content.contains("sk_live_123"));
+        let config: Config = serde_json::from_str(&content)?;
+        assert_eq!(config.app.len(), 2);
+        assert_eq!(config.app[0].item_type, crate::models::ItemType::Url);
+        assert!(config.app[1].secret);
+        let project = config.rpcfg.iter().find(|item| item.key == "project_name").unwrap();
+        assert_eq!(project.default, "shop");
+        let env = config.rpcfg.iter().find(|item| item.key == "environment").unwrap();
+        assert_eq!(env.default, "staging");
+
+        let options = InitOptions {
+            project: Some("bad/name".to_string()),
+            ..Default::default()
+        };
+        assert!(execute(output_path.to_str().unwrap(), &options, &mut Cursor::new(Vec::new()), &mut Vec::new()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_guard_merge_and_prompts() -> Result<()> {
+        let dir = TempDir::new()?;
+        let output_path = dir.path().join("repo_config.json");
+        let output_path = output_path.to_str().unwrap();
+
+        // Prompts fill in the defaults; an empty answer keeps the default
+        let options = InitOptions {
+            prompt: true,
+            ..Default::default()
+        };
+        let mut input = Cursor::new("shop\n\nbad/env\nstaging\n");
+        let mut output = Vec::new();
+        execute(output_path, &options, &mut input, &mut output)?;
+        assert!(String::from_utf8(output)?.contains("Invalid value"));
+        let config: Config = serde_json::from_str(&fs::read_to_string(output_path)?)?;
+        let default_of = |key: &str| config.rpcfg.iter().find(|item| item.key == key).unwrap().default.clone();
+        assert_eq!(default_of("project_name"), "shop");
+        assert_eq!(default_of("config_name"), "rpcfg_config");
+        assert_eq!(default_of("environment"), "staging");
+
+        // An existing file is not overwritten without --force
+        let err = execute(output_path, &InitOptions::default(), &mut Cursor::new(""), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+
+        // Merging adds the missing rpcfg items and keeps everything else
+        let mut config = config;
+        config.rpcfg.retain(|item| item.key != "config_version");
+        fs::write(output_path, serde_json::to_string_pretty(&config)?)?;
+        let options = InitOptions {
+            merge: true,
+            ..Default::default()
+        };
+        let result = execute(output_path, &options, &mut Cursor::new(""), &mut Vec::new())?;
+        assert!(result.message.contains("config_version"));
+        let merged: Config = serde_json::from_str(&fs::read_to_string(output_path)?)?;
+        assert_eq!(merged.rpcfg.len(), 5);
+        assert_eq!(merged.rpcfg.iter().find(|item| item.key == "project_name").unwrap().default, "shop");
+
+        let options = InitOptions {
+            force: true,
+            ..Default::default()
+        };
+        execute(output_path, &options, &mut Cursor::new(""), &mut Vec::new())?;
+        let replaced: Config = serde_json::from_str(&fs::read_to_string(output_path)?)?;
+        assert_eq!(replaced.rpcfg.iter().find(|item| item.key == "project_name").unwrap().default, "rpcfg");
+
+        // Items a child inherits are not missing, and only the defaults passed are overridden
+        let base = dir.path().join("base.json");
+        fs::write(&base, serde_json::to_string_pretty(&merged)?)?;
+        let child = dir.path().join("child.json");
+        let child = child.to_str().unwrap();
+        fs::write(child, r#"{"extends": "base.json", "rpcfg": [], "app": []}"#)?;
+        let options = InitOptions {
+            merge: true,
+            env: Some("prod".to_string()),
+            ..Default::default()
+        };
+        let result = execute(child, &options, &mut Cursor::new(""), &mut Vec::new())?;
+        assert_eq!(result.message, format!("Added environment, $schema to {}.", child));
+        let child_config = crate::compose::load_composed(child)?;
+        assert_eq!(child_config.get_settings("project_name")[0].default, "shop");
+        assert_eq!(child_config.get_settings("environment")[0].default, "prod");
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(child)?)?;
+        assert_eq!(saved["rpcfg"].as_array().unwrap().len(), 1);
+
         Ok(())
     }
 }
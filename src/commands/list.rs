@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use tabwriter::TabWriter;
+use tracing::{debug, info};
+
+use crate::files::{create_private_dir, stable_hash, write_atomic};
+use crate::models::{CommandResult, Config};
+use crate::rp_macros::storage_root;
+use crate::{snapshots, Success};
+
+/// The directory in each project under the storage root that points to the outputs of its
+/// `stored: "repo"` configurations, one file per output holding the output's path.
+pub const REPO_OUTPUTS_DIR: &str = ".repo";
+
+/// A saved configuration found under the storage root.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConfigEntry {
+    pub project: String,
+    pub config_name: String,
+    pub environment: String,
+    pub stored: String,
+    pub items: usize,
+    pub modified: String,
+    pub path: String,
+}
+
+/// Lists the saved configurations under a storage root.
+///
+/// Every `<project>/<config_name>-<environment>.json` output is read for its rpcfg values,
+/// number of items and last-modified time. Outputs of `stored: "repo"` configurations live
+/// next to their input files; they are found through the pointers `register_repo_output`
+/// leaves in the project, and skipped once the checkout holding them is gone.
+///
+/// # Arguments
+///
+/// * `root` - The storage root to scan.
+/// * `project` - Only list configurations of this project.
+/// * `env` - Only list configurations for this environment.
+/// * `json` - Write the list as JSON instead of a table.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the list.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if the root cannot be read or writing to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    root: &Path,
+    project: Option<&str>,
+    env: Option<&str>,
+    json: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let entries: Vec<ConfigEntry> = scan(root)?
+        .into_iter()
+        .filter(|entry| project.is_none_or(|project| entry.project == project))
+        .filter(|entry| env.is_none_or(|env| entry.environment == env))
+        .collect();
+
+    if json {
+        writeln!(output, "{}", serde_json::to_string_pretty(&entries)?)?;
+    } else if entries.is_empty() {
+        writeln!(output, "No configurations found under {}.", root.display())?;
+    } else {
+        let mut tw = TabWriter::new(vec![]);
+        writeln!(tw, "Project\tConfig\tEnvironment\tModified\tItems\tStored")?;
+        writeln!(tw, "-------\t------\t-----------\t--------\t-----\t------")?;
+        for entry in &entries {
+            writeln!(
+                tw,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                entry.project,
+                entry.config_name,
+                entry.environment,
+                entry.modified,
+                entry.items,
+                entry.stored
+            )?;
+        }
+        tw.flush()?;
+        output.write_all(&tw.into_inner()?)?;
+    }
+
+    info!("Listed {} configurations", entries.len());
+
+    Ok(Success!("Found {} configurations.", entries.len()))
+}
+
+/// Reads every saved configuration under the root, sorted by project, config name and environment.
+pub fn scan(root: &Path) -> Result<Vec<ConfigEntry>> {
+    let mut entries = Vec::new();
+    if !root.is_dir() {
+        return Ok(entries);
+    }
+
+    for project_dir in fs::read_dir(root)
+        .with_context(|| format!("Failed to read directory: {}", root.display()))?
+    {
+        let project_dir = project_dir?.path();
+        if !project_dir.is_dir() || is_hidden(&project_dir) {
+            continue;
+        }
+        for file in fs::read_dir(&project_dir)? {
+            let path = file?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                match read_entry(&project_dir, &path) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => debug!("Skipping {}: {:#}", path.display(), e),
+                }
+            }
+        }
+        for path in repo_outputs(&project_dir)? {
+            match read_entry(&project_dir, &path) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => debug!("Skipping {}: {:#}", path.display(), e),
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        (&a.project, &a.config_name, &a.environment).cmp(&(&b.project, &b.config_name, &b.environment))
+    });
+    Ok(entries)
+}
+
+/// Returns the `stored: "repo"` outputs a project points to that still exist.
+fn repo_outputs(project_dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = project_dir.join(REPO_OUTPUTS_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut outputs = Vec::new();
+    for pointer in fs::read_dir(&dir)? {
+        let path = PathBuf::from(fs::read_to_string(pointer?.path())?.trim());
+        if path.is_file() {
+            outputs.push(path);
+        } else {
+            debug!("{} no longer exists", path.display());
+        }
+    }
+    Ok(outputs)
+}
+
+/// Records where a `stored: "repo"` configuration saved its JSON output, so `list` can find it.
+///
+/// The pointer lives in the project's `REPO_OUTPUTS_DIR` under the storage root and is named
+/// by a hash of the output's absolute path, so every checkout of a repository gets its own.
+///
+/// # Errors
+///
+/// This function will return an error if the storage root cannot be found or the pointer
+/// cannot be written.
+pub fn register_repo_output(config: &Config, json_path: &Path) -> Result<()> {
+    let project = config
+        .get_settings("project_name")
+        .first()
+        .map(|item| item.value.clone())
+        .unwrap_or_default();
+    let dir = storage_root(config)?.join(project).join(REPO_OUTPUTS_DIR);
+    let json_path = fs::canonicalize(json_path)
+        .with_context(|| format!("Failed to resolve {}", json_path.display()))?;
+    let json_path = json_path.to_string_lossy();
+    let pointer = dir.join(stable_hash(&json_path));
+    if fs::read_to_string(&pointer).ok().as_deref() != Some(&json_path) {
+        create_private_dir(&dir)?;
+        write_atomic(&pointer, &json_path)?;
+    }
+    Ok(())
+}
+
+fn read_entry(project_dir: &Path, path: &Path) -> Result<ConfigEntry> {
+    let values: HashMap<String, String> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let modified = fs::metadata(path)?.modified()?;
+
+    // Older outputs may lack an rpcfg value; the directory and file names hold the same information
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let (file_config, file_env) = stem.rsplit_once('-').unwrap_or((&stem, ""));
+    let value_or = |key: &str, fallback: &str| {
+        values
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| fallback.to_string())
+    };
+
+    Ok(ConfigEntry {
+        project: value_or(
+            "project_name",
+            &project_dir.file_name().unwrap_or_default().to_string_lossy(),
+        ),
+        config_name: value_or("config_name", file_config),
+        environment: value_or("environment", file_env),
+        stored: value_or("stored", "local"),
+        items: values.len(),
+        modified: snapshots::format_display_time(modified),
+        path: path.display().to_string(),
+    })
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collect::save_configuration;
+    use crate::{create_test_input_file, get_rp_dir, safe_test};
+    use std::io::Cursor;
+
+    safe_test!(test_list_configurations, {
+        let (mut config, temp_dir) = create_test_input_file!("list");
+        let project = config.rpcfg[2].value.clone();
+        save_configuration(&config, false)?;
+        config.rpcfg[4].value = "prod".to_string();
+        save_configuration(&config, false)?;
+
+        let root = get_rp_dir!(&config)?;
+        let mut output = Vec::new();
+        execute(&root, Some(&project), None, false, &mut Cursor::new(""), &mut output)?;
+        let table = String::from_utf8(output)?;
+        assert_eq!(table.lines().filter(|line| line.starts_with(&project)).count(), 2);
+
+        let mut output = Vec::new();
+        execute(&root, Some(&project), Some("prod"), true, &mut Cursor::new(""), &mut output)?;
+        let entries: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(entries.as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["environment"], "prod");
+        assert_eq!(entries[0]["items"], 7);
+        assert_eq!(entries[0]["stored"], "local");
+
+        // Outputs stored next to the input file are listed too
+        config.rpcfg[0].value = "repo".to_string();
+        config.rpcfg[4].value = "checkout".to_string();
+        save_configuration(&config, false)?;
+        let mut output = Vec::new();
+        execute(&root, Some(&project), Some("checkout"), true, &mut Cursor::new(""), &mut output)?;
+        let entries: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(entries.as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["stored"], "repo");
+        let checkout = fs::canonicalize(temp_dir.path())?;
+        assert!(entries[0]["path"].as_str().unwrap().starts_with(&*checkout.to_string_lossy()));
+
+        Ok(())
+    });
+}
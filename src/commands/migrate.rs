@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::files::write_atomic;
+use crate::migrate::{self, CURRENT_FORMAT_VERSION};
+use crate::models::{CommandResult, Config};
+use crate::Success;
+
+/// Upgrades an input file to the current format version, keeping a backup of the original.
+///
+/// The original is copied to `<file>.v<version>.bak` before the upgraded file replaces it.
+/// Only the named file is upgraded; files it names in `extends` and `include` are upgraded
+/// in memory whenever they are loaded, and can be migrated one at a time.
+///
+/// # Arguments
+///
+/// * `input_file` - The path of the input file to upgrade.
+/// * `dry_run` - List the changes without writing anything.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the changes.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The file cannot be read, parsed or upgraded, for example because a newer rpcfg wrote it.
+/// * The backup or the upgraded file cannot be written.
+pub fn execute<R: BufRead, W: Write>(
+    input_file: &str,
+    dry_run: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let content = fs::read_to_string(input_file)
+        .with_context(|| format!("Failed to open file: {}", input_file))?;
+    let mut file: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse JSON from file: {}", input_file))?;
+    let version = migrate::format_version(&file)?;
+    let changes = migrate::upgrade(&mut file).with_context(|| format!("Failed to upgrade file: {}", input_file))?;
+    // The upgraded file must still load, but it is written as JSON so keys rpcfg does not model survive
+    serde_json::from_value::<Config>(file.clone())
+        .with_context(|| format!("Failed to parse JSON from file: {}", input_file))?;
+    if changes.is_empty() {
+        writeln!(output, "{} is already at format version {}.", input_file, CURRENT_FORMAT_VERSION)?;
+        return Ok(Success!("Nothing to migrate."));
+    }
+
+    for change in &changes {
+        writeln!(output, "  {}", change)?;
+    }
+    if dry_run {
+        writeln!(output, "Dry run: {} was not changed.", input_file)?;
+        return Ok(Success!("{} changes to make.", changes.len()));
+    }
+
+    let backup = format!("{}.v{}.bak", input_file, version);
+    fs::copy(input_file, &backup).with_context(|| format!("Failed to back up {} to {}", input_file, backup))?;
+    write_atomic(Path::new(input_file), &serde_json::to_string_pretty(&file)?)
+        .with_context(|| format!("Failed to write configuration file: {}", input_file))?;
+
+    writeln!(
+        output,
+        "Migrated {} from format version {} to {}; the original is at {}",
+        input_file, version, CURRENT_FORMAT_VERSION, backup
+    )?;
+    info!("Migrated {} to format version {}", input_file, CURRENT_FORMAT_VERSION);
+    Ok(Success!("Migrated {} to format version {}.", input_file, CURRENT_FORMAT_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    safe_test!(test_migrate_command, {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("repo_config.json");
+        let path = path.to_str().unwrap();
+        let original = r#"{"rpcfg": [{"key": "project_name", "description": "Name", "default": "shop"}], "app": [], "x-owner": "team-a"}"#;
+        fs::write(path, original)?;
+
+        execute(path, true, &mut Cursor::new(""), &mut Vec::new())?;
+        assert_eq!(fs::read_to_string(path)?, original);
+
+        let mut output = Vec::new();
+        execute(path, false, &mut Cursor::new(""), &mut output)?;
+        assert!(String::from_utf8(output)?.contains("from format version 0 to 1"));
+        assert_eq!(fs::read_to_string(format!("{}.v0.bak", path))?, original);
+        let migrated: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        assert_eq!(migrated["format_version"], CURRENT_FORMAT_VERSION);
+        assert_eq!(migrated["rpcfg"][1]["key"], "stored");
+        // Keys rpcfg does not know are kept
+        assert_eq!(migrated["x-owner"], "team-a");
+
+        let result = execute(path, false, &mut Cursor::new(""), &mut Vec::new())?;
+        assert_eq!(result.message, "Nothing to migrate.");
+
+        Ok(())
+    });
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::files::{create_private_dir, move_path};
+use crate::models::CommandResult;
+use crate::templates::TEMPLATES_DIR;
+use crate::Success;
+
+/// Moves the saved outputs and snapshots from an old storage root to a new one.
+///
+/// This is the migration path from the legacy `~/.rpcfg` root. Every project directory in
+/// `from` is moved into `to`, and the user templates into `config_to`; nothing is moved if
+/// an entry already exists in both places.
+///
+/// # Arguments
+///
+/// * `from` - The storage root to move from.
+/// * `to` - The storage root to move to.
+/// * `config_to` - The config directory to move the user templates to (see `rp_macros::resolve_config_dir`).
+/// * `no_prompt` - Skip the confirmation prompt.
+/// * `input` - A mutable reference to a BufRead trait object for reading the confirmation.
+/// * `output` - A mutable reference to a Write trait object for writing prompts and messages.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * A project directory exists in both roots.
+/// * A directory cannot be created, read or moved.
+pub fn execute<R: BufRead, W: Write>(
+    from: &Path,
+    to: &Path,
+    config_to: &Path,
+    no_prompt: bool,
+    input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    if !from.is_dir() {
+        return Ok(Success!("Nothing to relocate: {} does not exist.", from.display()));
+    }
+    if from == to {
+        return Ok(Success!("{} is already the storage root.", to.display()));
+    }
+
+    let entries: Vec<_> = fs::read_dir(from)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name())
+        .collect();
+    let destination = |name: &OsString| {
+        if name == TEMPLATES_DIR {
+            config_to.join(name)
+        } else {
+            to.join(name)
+        }
+    };
+    let conflicts: Vec<String> = entries
+        .iter()
+        .map(destination)
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if !conflicts.is_empty() {
+        anyhow::bail!("{} already exist; move or remove them first", conflicts.join(", "));
+    }
+
+    if !no_prompt {
+        writeln!(output, "Move {} to {}? (y/N)", from.display(), to.display())?;
+        let mut response = String::new();
+        input.read_line(&mut response)?;
+
+        if !matches!(response.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Ok(Success!("Relocation cancelled."));
+        }
+    }
+
+    create_private_dir(to)?;
+    for name in &entries {
+        let target = destination(name);
+        debug!("Moving {:?} to {}", name, target.display());
+        if let Some(parent) = target.parent() {
+            create_private_dir(parent)?;
+        }
+        move_path(&from.join(name), &target)?;
+    }
+    fs::remove_dir(from)?;
+    info!("Relocated {} to {}", from.display(), to.display());
+
+    Ok(Success!(
+        "Moved {} entries from {} to {}.",
+        entries.len(),
+        from.display(),
+        to.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    safe_test!(test_relocate, {
+        let dir = TempDir::new()?;
+        let legacy = dir.path().join(".rpcfg");
+        let root = dir.path().join("share").join("rpcfg");
+        let config_dir = dir.path().join("config").join("rpcfg");
+        fs::create_dir_all(legacy.join("project"))?;
+        fs::create_dir_all(legacy.join(TEMPLATES_DIR))?;
+        fs::write(legacy.join("project").join("config-dev.json"), "{}")?;
+        fs::create_dir_all(root.join("other"))?;
+
+        // A conflicting project stops the move before anything is changed
+        fs::create_dir_all(root.join("project"))?;
+        let err = execute(&legacy, &root, &config_dir, true, &mut Cursor::new(""), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("project"));
+        assert!(legacy.join("project").join("config-dev.json").exists());
+        fs::remove_dir(root.join("project"))?;
+
+        execute(&legacy, &root, &config_dir, false, &mut Cursor::new("y\n"), &mut Vec::new())?;
+        assert!(!legacy.exists());
+        assert!(root.join("project").join("config-dev.json").exists());
+        assert!(root.join("other").exists());
+        assert!(config_dir.join(TEMPLATES_DIR).is_dir());
+        assert!(!root.join(TEMPLATES_DIR).exists());
+
+        Ok(())
+    });
+}
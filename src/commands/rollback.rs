@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use tracing::{debug, info};
+
+use crate::models::{CommandResult, Config, Status};
+use crate::{env_output_uri, files, json_output_uri, snapshots};
+
+/// Restores the JSON and ENV output files from a snapshot.
+///
+/// The restore is itself recorded as a new snapshot, so it can be rolled back too.
+/// The input file is not changed.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the Config object whose outputs are restored.
+/// * `id` - The id of the snapshot to restore, as printed by `history`.
+/// * `no_prompt` - Skip the confirmation prompt.
+/// * `input` - A mutable reference to a BufRead trait object for reading the confirmation.
+/// * `output` - A mutable reference to a Write trait object for writing prompts and messages.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * There is no snapshot with the given id.
+/// * The snapshot cannot be read or the output files cannot be written.
+pub fn execute<R: BufRead, W: Write>(
+    config: &Config,
+    id: &str,
+    no_prompt: bool,
+    input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    // Fail on an unknown id before asking for confirmation
+    snapshots::find(config, id)?;
+    let json_path = json_output_uri!(config)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get JSON output path"))?;
+    let env_path = env_output_uri!(config)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get ENV output path"))?;
+
+    if !no_prompt {
+        writeln!(output, "Restore snapshot {} over {}? (y/N)", id, json_path)?;
+        let mut response = String::new();
+        input.read_line(&mut response)?;
+
+        if !matches!(response.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Ok(CommandResult {
+                status: Status::Ok,
+                message: "Rollback cancelled.".to_string(),
+                env_file: None,
+                json_file: None,
+            });
+        }
+    }
+
+    // Look the snapshot up again and read it under the lock, so a save running at the same time
+    // cannot prune or replace it before it is restored
+    let _lock = files::lock_project(config)?;
+    let snapshot = snapshots::find(config, id)?;
+    let json_content = fs::read_to_string(&snapshot.json_path)
+        .with_context(|| format!("Failed to read snapshot: {}", snapshot.json_path.display()))?;
+    let env_content = fs::read_to_string(&snapshot.env_path).unwrap_or_default();
+    let env_content = files::stamp_env(&json_content, &env_content);
+    debug!("Restoring snapshot {} to {} and {}", id, json_path, env_path);
+
+    files::write_all_or_none(&[
+        (PathBuf::from(&json_path), json_content.clone()),
+        (PathBuf::from(&env_path), env_content.clone()),
+    ])?;
+    let recorded = snapshots::record(config, &json_content, &env_content)?;
+
+    info!("Rolled back to snapshot {}", id);
+
+    Ok(CommandResult {
+        status: Status::Ok,
+        message: format!("Restored snapshot {} (recorded as {}).", id, recorded.id),
+        env_file: Some(env_path),
+        json_file: Some(json_path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{collect, history};
+    use crate::{create_test_input_file, safe_test};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    safe_test!(test_history_and_rollback, {
+        let (mut config, _temp_dir) = create_test_input_file!("rollback");
+        config.app[1].secret = true;
+        let key = config.app[0].key.clone();
+
+        // Two saves with different values
+        collect::collect_user_input(&mut config, &mut Cursor::new("6\nfirst\ns\n"), &mut Vec::new())?;
+        collect::collect_user_input(&mut config, &mut Cursor::new("6\nsecond\n7\nhidden\ns\n"), &mut Vec::new())?;
+
+        let list = snapshots::list(&config)?;
+        assert_eq!(list.len(), 2);
+
+        // History shows the newest first with masked secrets
+        let mut output = Cursor::new(Vec::new());
+        history::execute(&config, &mut Cursor::new(Vec::new()), &mut output)?;
+        let output_str = String::from_utf8(output.into_inner())?;
+        assert!(output_str.starts_with(&list[1].id));
+        assert!(output_str.contains(&format!("~ {}: 'first' -> 'second'", key)));
+        assert!(output_str.contains("'********'"));
+        assert!(!output_str.contains("hidden"));
+
+        // Cancelled rollback leaves the output alone
+        let result = execute(&config, &list[0].id, false, &mut Cursor::new("n\n"), &mut Vec::new())?;
+        assert_eq!(result.message, "Rollback cancelled.");
+
+        // Rolling back restores the first save and records a new snapshot
+        execute(&config, &list[0].id, true, &mut Cursor::new(""), &mut Vec::new())?;
+        let json_path = json_output_uri!(&config).expect("Failed to construct JSON output path");
+        let restored: HashMap<String, String> = serde_json::from_str(&fs::read_to_string(json_path)?)?;
+        assert_eq!(restored[&key], "first");
+        assert_eq!(snapshots::list(&config)?.len(), 3);
+
+        assert!(execute(&config, "missing", true, &mut Cursor::new(""), &mut Vec::new()).is_err());
+
+        Ok(())
+    });
+
+    safe_test!(test_history_retention_limit, {
+        let (mut config, _temp_dir) = create_test_input_file!("retention");
+        config.rpcfg.push(crate::models::ConfigItem {
+            key: "history_limit".to_string(),
+            description: "Number of snapshots to keep".to_string(),
+            default: "2".to_string(),
+            value: "2".to_string(),
+            ..Default::default()
+        });
+
+        let key = config.app[0].key.clone();
+        for value in ["a", "b", "c"] {
+            let mut input = Cursor::new(format!("{}\n{}\ns\n", key, value));
+            collect::collect_user_input(&mut config, &mut input, &mut Vec::new())?;
+        }
+
+        let list = snapshots::list(&config)?;
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].values()?[&key], "b");
+
+        // A limit of 0 is rejected, and still keeps the snapshot just recorded
+        let limit = config.rpcfg.last_mut().unwrap();
+        assert!(limit.validate_value("0").is_err());
+        limit.value = "0".to_string();
+        let mut input = Cursor::new(format!("{}\nd\ns\n", key));
+        collect::collect_user_input(&mut config, &mut input, &mut Vec::new())?;
+        let list = snapshots::list(&config)?;
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].values()?[&key], "d");
+
+        Ok(())
+    });
+}
@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::files::write_atomic;
+use crate::models::{CommandResult, Config};
+use crate::Success;
+
+/// The name of the schema file `init` writes next to the input file.
+pub const SCHEMA_FILE_NAME: &str = "rpcfg.schema.json";
+
+/// Generates the JSON Schema of the input file format from the `Config` and `ConfigItem` types.
+///
+/// Fields rpcfg never reads from the input file, such as the collected values, are left out.
+pub fn generate() -> Result<String> {
+    let schema = schemars::schema_for!(Config);
+    Ok(format!("{}\n", serde_json::to_string_pretty(&schema)?))
+}
+
+/// Writes the JSON Schema next to an input file, returning the `$schema` reference to it.
+///
+/// The reference is relative, so it stays valid when the repository is cloned elsewhere.
+///
+/// # Errors
+///
+/// This function will return an error if the schema file cannot be written.
+pub fn write_next_to(input_file: &str) -> Result<String> {
+    let dir = match Path::new(input_file).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let path = dir.join(SCHEMA_FILE_NAME);
+    let schema = generate()?;
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(schema.as_str()) {
+        write_atomic(&path, &schema)
+            .with_context(|| format!("Failed to write schema file: {}", path.display()))?;
+    }
+    Ok(format!("./{}", SCHEMA_FILE_NAME))
+}
+
+/// Emits the JSON Schema of the input file format.
+///
+/// # Arguments
+///
+/// * `output_path` - The file to write the schema to; the schema goes to `output` when None.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the schema or the confirmation message.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if the schema file cannot be written or writing to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    output_path: Option<&str>,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let schema = generate()?;
+    match output_path {
+        Some(path) => {
+            write_atomic(Path::new(path), &schema)
+                .with_context(|| format!("Failed to write schema file: {}", path))?;
+            writeln!(output, "JSON Schema written to {}", path)?;
+            info!("Wrote the JSON Schema to {}", path);
+        }
+        None => write!(output, "{}", schema)?,
+    }
+    Ok(Success!("Generated the JSON Schema."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConfigItem;
+    use crate::safe_test;
+    use std::collections::BTreeMap;
+
+    safe_test!(test_schema_matches_the_types, {
+        // The committed schema must be regenerated when the input file format changes
+        let committed = include_str!("../../rpcfg.schema.json");
+        assert!(
+            committed == generate()?,
+            "rpcfg.schema.json is out of date; run `cargo run -- schema -o rpcfg.schema.json`"
+        );
+
+        // Every field an item can be written with is described
+        let item = ConfigItem {
+            secret: true,
+            item_type: crate::models::ItemType::Integer,
+            defaults: BTreeMap::from([("prod".to_string(), "1".to_string())]),
+            ..Default::default()
+        };
+        let schema: serde_json::Value = serde_json::from_str(committed)?;
+        let properties = &schema["definitions"]["ConfigItem"]["properties"];
+        for key in serde_json::to_value(&item)?.as_object().unwrap().keys() {
+            assert!(properties.get(key).is_some(), "{} is missing from the schema", key);
+        }
+        assert!(schema["properties"].get("$schema").is_some());
+
+        Ok(())
+    });
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use tabwriter::TabWriter;
+use tracing::info;
+
+use crate::models::{CommandResult, Config};
+use crate::Success;
+
+/// Shows the resolved configuration as a table.
+///
+/// Each row lists the key, the resolved value and the layer that supplied it
+/// (default, shellscript, saved, env or cli), so it is easy to see why a value is what it is.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the resolved Config object.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the table.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if writing to the output stream fails.
+pub fn execute<R: BufRead, W: Write>(
+    config: &Config,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let mut tw = TabWriter::new(vec![]);
+
+    writeln!(tw, "Key\tValue\tSource")?;
+    writeln!(tw, "---\t-----\t------")?;
+
+    for item in config.rpcfg.iter().chain(config.app.iter()) {
+        writeln!(tw, "{}\t{}\t{}", item.key, item.masked(&item.value), item.source)?;
+    }
+    tw.flush()?;
+
+    output.write_all(&tw.into_inner()?)?;
+
+    info!("Successfully showed configuration");
+
+    Ok(Success!("Configuration shown successfully."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::resolve_values;
+    use crate::{create_test_input_file, safe_test};
+    use std::io::Cursor;
+
+    safe_test!(test_show_sources, {
+        let (mut config, _temp_dir) = create_test_input_file!("show_sources");
+        let key = config.app[1].key.clone();
+        config.app[0].secret = true;
+        config.app[0].value = "hunter2".to_string();
+
+        resolve_values(&mut config, &[(key.clone(), "from_cli".to_string())])?;
+
+        let mut output = Cursor::new(Vec::new());
+        execute(&config, &mut Cursor::new(Vec::new()), &mut output)?;
+
+        let output_str = String::from_utf8(output.into_inner())?;
+        let row = output_str
+            .lines()
+            .find(|line| line.starts_with(&key))
+            .expect("row for the overridden key");
+        assert!(row.contains("from_cli"));
+        assert!(row.trim_end().ends_with("cli"));
+        assert!(output_str.contains("default"));
+        assert!(!output_str.contains("hunter2"));
+
+        Ok(())
+    });
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tabwriter::TabWriter;
+use tracing::info;
+
+use crate::compose::load_composed;
+use crate::models::CommandResult;
+use crate::templates::{self, Template};
+use crate::Success;
+
+/// Lists the built-in and user templates.
+///
+/// # Arguments
+///
+/// * `config_dir` - The config directory holding the user templates.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the list.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if a user template cannot be read or writing to the output stream fails.
+pub fn list<R: BufRead, W: Write>(config_dir: &Path, _input: &mut R, output: &mut W) -> Result<CommandResult> {
+    let templates = templates::list(config_dir)?;
+
+    let mut tw = TabWriter::new(vec![]);
+    writeln!(tw, "Name\tSource\tItems\tDescription")?;
+    writeln!(tw, "----\t------\t-----\t-----------")?;
+    for template in &templates {
+        let source = if template.builtin { "built-in" } else { "user" };
+        writeln!(
+            tw,
+            "{}\t{}\t{}\t{}",
+            template.name,
+            source,
+            template.app.len(),
+            template.description
+        )?;
+    }
+    tw.flush()?;
+    output.write_all(&tw.into_inner()?)?;
+
+    info!("Listed {} templates", templates.len());
+    Ok(Success!("Found {} templates.", templates.len()))
+}
+
+/// Writes a template as JSON, in the form `template add` and the user template directory use.
+///
+/// # Arguments
+///
+/// * `config_dir` - The config directory holding the user templates.
+/// * `name` - The name of the template.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the template.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if there is no such template or writing to the output stream fails.
+pub fn show<R: BufRead, W: Write>(config_dir: &Path, name: &str, _input: &mut R, output: &mut W) -> Result<CommandResult> {
+    let template = templates::find(config_dir, name)?;
+    writeln!(output, "{}", serde_json::to_string_pretty(&template)?)?;
+    Ok(Success!("Showed template {}.", name))
+}
+
+/// Saves the app items of a schema file as a user template.
+///
+/// Files named by `extends` and `include` are merged in first, so the template holds every
+/// app item the schema uses. The rpcfg items are not part of a template.
+///
+/// # Arguments
+///
+/// * `config_dir` - The config directory holding the user templates.
+/// * `name` - The name of the new template.
+/// * `from` - The schema file to take the app items from.
+/// * `description` - The description of the template; defaults to naming the schema file.
+/// * `force` - Replace a user template with the same name.
+/// * `_input` - A mutable reference to a BufRead trait object. Not used in this function but included for consistency.
+/// * `output` - A mutable reference to a Write trait object for writing the confirmation message.
+///
+/// # Returns
+///
+/// Returns a Result containing a CommandResult on success, or an error if the operation fails.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The schema file cannot be loaded.
+/// * The name is not valid, or the template exists and `force` is false.
+/// * The template cannot be written.
+pub fn add<R: BufRead, W: Write>(
+    config_dir: &Path,
+    name: &str,
+    from: &str,
+    description: Option<&str>,
+    force: bool,
+    _input: &mut R,
+    output: &mut W,
+) -> Result<CommandResult> {
+    let schema = load_composed(from)?;
+    let template = Template {
+        name: name.to_string(),
+        builtin: false,
+        description: description.map_or_else(|| format!("From {}", from), str::to_string),
+        app: schema.app,
+    };
+    let path = templates::add(config_dir, &template, force)?;
+
+    writeln!(
+        output,
+        "Saved template {} with {} items to {}",
+        name,
+        template.app.len(),
+        path.display()
+    )?;
+    info!("Saved template {} to {}", name, path.display());
+    Ok(Success!("Saved template {}.", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::init::{self, InitOptions};
+    use crate::models::Config;
+    use crate::safe_test;
+    use std::fs;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    safe_test!(test_template_commands, {
+        let dir = TempDir::new()?;
+        let root = dir.path().join("root");
+        let schema_path = dir.path().join("repo_config.json");
+        let schema_path = schema_path.to_str().unwrap();
+
+        // Start a schema from a built-in template, then save it as a user template
+        let options = InitOptions {
+            template: Some(templates::find(&root, "azure")?),
+            ..Default::default()
+        };
+        init::execute(schema_path, &options, &mut Cursor::new(""), &mut Vec::new())?;
+        let config: Config = serde_json::from_str(&fs::read_to_string(schema_path)?)?;
+        assert!(config.app.iter().any(|item| item.key == "azure_client_secret" && item.secret));
+
+        add(&root, "our-azure", schema_path, None, false, &mut Cursor::new(""), &mut Vec::new())?;
+        let mut output = Vec::new();
+        list(&root, &mut Cursor::new(""), &mut output)?;
+        let listed = String::from_utf8(output)?;
+        assert!(listed.contains("our-azure"));
+        assert!(listed.contains("built-in"));
+
+        let mut output = Vec::new();
+        show(&root, "our-azure", &mut Cursor::new(""), &mut output)?;
+        let shown: Template = serde_json::from_slice(&output)?;
+        assert_eq!(shown.app.len(), config.app.len());
+
+        Ok(())
+    });
+}
@@ -52,6 +52,12 @@ impl SubscriberGuard {
     }
 }
 
+impl Default for SubscriberGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Runs a test function with proper setup and error handling.
 ///
 /// This function creates a SubscriberGuard to ensure logging is set up,
@@ -87,7 +93,7 @@ pub fn run_test<T>(test: T) -> Result<(), String>
 where
     T: FnOnce() -> Result<(), anyhow::Error> + panic::UnwindSafe,
 {
-    let guard = SubscriberGuard::new();
+    let _guard = SubscriberGuard::new();
     let result: Result<Result<(), anyhow::Error>, Box<dyn std::any::Any + Send>> = panic::catch_unwind(|| {
         test().map_err(|e| {
             let bt = Backtrace::capture();
@@ -104,7 +110,6 @@ where
             )
         })
     });
-    drop(guard);
 
     match result {
         Ok(Ok(())) => Ok(()),
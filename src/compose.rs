@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::migrate;
+use crate::models::{Config, ConfigItem};
+
+/// Loads a schema file and merges in the files it names in `extends` and `include`.
+///
+/// Base files are merged first, `extends` before the `include` list, and each later file
+/// overrides earlier ones by key: an item with a key that already exists replaces it in place,
+/// new keys are appended. The file's own items are merged last, so they always win.
+/// Relative paths are resolved against the directory of the file that names them.
+///
+/// Every item records the file it came from in `defined_in`, and error messages name that file.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the top-level schema file.
+///
+/// # Returns
+///
+/// * `Result<Config>` - The merged configuration. `extends` and `include` are those of the top-level file.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * Any of the files cannot be opened or parsed.
+/// * The files include each other in a cycle.
+/// * A key is defined as an rpcfg item in one file and as an app item in another.
+pub fn load_composed(file_path: &str) -> Result<Config> {
+    let mut stack = Vec::new();
+    let config = load_recursive(Path::new(file_path), file_path, &mut stack)?;
+    check_sections(&config)?;
+    Ok(config)
+}
+
+/// Loads one file and, depth first, the files it builds on.
+fn load_recursive(path: &Path, name: &str, stack: &mut Vec<PathBuf>) -> Result<Config> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Failed to open file: {}", name))?;
+    if let Some(start) = stack.iter().position(|p| *p == canonical) {
+        let cycle: Vec<String> = stack[start..]
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        anyhow::bail!("Schema include cycle detected: {}", cycle.join(" -> "));
+    }
+
+    let (mut config, _) = migrate::read_upgraded(path, name)?;
+    for item in config.rpcfg.iter_mut().chain(config.app.iter_mut()) {
+        item.defined_in = name.to_string();
+    }
+
+    if config.extends.is_none() && config.include.is_empty() {
+        return Ok(config);
+    }
+
+    stack.push(canonical);
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut merged = Config {
+        rpcfg: Vec::new(),
+        app: Vec::new(),
+        ..config.clone()
+    };
+    for base in config.extends.iter().chain(config.include.iter()) {
+        let base_path = base_dir.join(base);
+        debug!("{} builds on {}", name, base_path.display());
+        let base_config = load_recursive(&base_path, &base_path.to_string_lossy(), stack)
+            .with_context(|| format!("Failed to load '{}' referenced by {}", base, name))?;
+        merge_items(&mut merged.rpcfg, base_config.rpcfg);
+        merge_items(&mut merged.app, base_config.app);
+    }
+    stack.pop();
+
+    merge_items(&mut merged.rpcfg, config.rpcfg);
+    merge_items(&mut merged.app, config.app);
+    Ok(merged)
+}
+
+/// Merges `overlay` into `target` by key.
+fn merge_items(target: &mut Vec<ConfigItem>, overlay: Vec<ConfigItem>) {
+    for item in overlay {
+        match target.iter_mut().find(|existing| existing.key == item.key) {
+            Some(existing) => {
+                debug!(
+                    "'{}' from {} overrides the one from {}",
+                    item.key, item.defined_in, existing.defined_in
+                );
+                *existing = item;
+            }
+            None => target.push(item),
+        }
+    }
+}
+
+/// Rejects keys that end up in both the rpcfg and app sections.
+fn check_sections(config: &Config) -> Result<()> {
+    for rpcfg_item in &config.rpcfg {
+        if let Some(app_item) = config.app.iter().find(|item| item.key == rpcfg_item.key) {
+            anyhow::bail!(
+                "Key '{}' is defined as an rpcfg item in {} and as an app item in {}",
+                rpcfg_item.key,
+                rpcfg_item.defined_in,
+                app_item.defined_in
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collect::save_configuration;
+    use crate::safe_test;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_json(dir: &TempDir, name: &str, value: serde_json::Value) -> String {
+        let path = dir.path().join(name);
+        fs::write(&path, serde_json::to_string_pretty(&value).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn item(key: &str, default: &str) -> serde_json::Value {
+        json!({ "key": key, "description": key, "default": default })
+    }
+
+    safe_test!(test_extends_and_include, {
+        let dir = TempDir::new()?;
+        write_json(
+            &dir,
+            "base.json",
+            json!({ "rpcfg": [item("project_name", "base")], "app": [item("location", "westus"), item("tenant", "t1")] }),
+        );
+        write_json(
+            &dir,
+            "extra.json",
+            json!({ "rpcfg": [], "app": [item("tenant", "t2"), item("subscription", "s1")] }),
+        );
+        let child = write_json(
+            &dir,
+            "child.json",
+            json!({ "extends": "base.json", "include": ["extra.json"], "rpcfg": [], "app": [item("location", "eastus")] }),
+        );
+
+        let mut config = load_composed(&child)?;
+        let keys: Vec<&str> = config.app.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, vec!["location", "tenant", "subscription"]);
+        assert_eq!(config.app[0].default, "eastus");
+        assert_eq!(config.app[0].defined_in, child);
+        assert_eq!(config.app[1].default, "t2");
+        assert!(config.app[1].defined_in.ends_with("extra.json"));
+        assert_eq!(config.rpcfg[0].default, "base");
+
+        // Saving the input file keeps the references and leaves inherited items out
+        config.input_file = child.clone();
+        config.is_test = true;
+        config.validate_rpcfg_config()?;
+        save_configuration(&config, true)?;
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&child)?)?;
+        assert_eq!(saved["extends"], "base.json");
+        assert_eq!(saved["app"].as_array().unwrap().len(), 1);
+
+        Ok(())
+    });
+
+    safe_test!(test_extends_keeps_base_storage, {
+        let dir = TempDir::new()?;
+        write_json(
+            &dir,
+            "base.json",
+            json!({ "rpcfg": [item("stored", "repo"), item("project_name", "shop")], "app": [] }),
+        );
+        let child = write_json(
+            &dir,
+            "child.json",
+            json!({ "extends": "base.json", "rpcfg": [item("environment", "prod")], "app": [] }),
+        );
+
+        let config = load_composed(&child)?;
+        let stored = config.get_settings("stored");
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].default, "repo");
+
+        Ok(())
+    });
+
+    safe_test!(test_include_cycle, {
+        let dir = TempDir::new()?;
+        write_json(&dir, "a.json", json!({ "extends": "b.json", "rpcfg": [], "app": [] }));
+        let b = write_json(&dir, "b.json", json!({ "extends": "a.json", "rpcfg": [], "app": [] }));
+
+        let err = load_composed(&b).unwrap_err();
+        assert!(format!("{:#}", err).contains("cycle"));
+
+        Ok(())
+    });
+
+    safe_test!(test_section_conflict_names_files, {
+        let dir = TempDir::new()?;
+        write_json(&dir, "base.json", json!({ "rpcfg": [item("location", "x")], "app": [] }));
+        let child = write_json(
+            &dir,
+            "child.json",
+            json!({ "extends": "base.json", "rpcfg": [], "app": [item("location", "y")] }),
+        );
+
+        let message = load_composed(&child).unwrap_err().to_string();
+        assert!(message.contains("base.json"));
+        assert!(message.contains("child.json"));
+
+        Ok(())
+    });
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+
+/// Parses the contents of a `.env` file into its variables, in file order.
+///
+/// Each line is `NAME=VALUE`, optionally preceded by `export `. Blank lines and lines
+/// starting with `#` are skipped. Values may be wrapped in single quotes (taken literally)
+/// or double quotes (`\n`, `\t`, `\"` and `\\` are unescaped); an unquoted value ends at
+/// ` #`, which starts a comment. A later definition of a name replaces the earlier one.
+///
+/// # Errors
+///
+/// This function will return an error, naming the line, if a line is not a valid assignment
+/// or a quoted value is not closed.
+pub fn parse(content: &str) -> Result<Vec<(String, String)>> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Line {}: expected NAME=VALUE", number + 1))?;
+        let name = name.trim();
+        if !is_valid_name(name) {
+            anyhow::bail!("Line {}: '{}' is not a valid variable name", number + 1, name);
+        }
+        let value = parse_value(value.trim())
+            .ok_or_else(|| anyhow::anyhow!("Line {}: unterminated quoted value", number + 1))?;
+
+        match vars.iter_mut().find(|(existing, _)| existing == name) {
+            Some(var) => var.1 = value,
+            None => vars.push((name.to_string(), value)),
+        }
+    }
+    Ok(vars)
+}
+
+/// Returns true if the name can be used as an environment variable name.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest.find('\'').map(|end| rest[..end].to_string());
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(parsed),
+                '\\' => match chars.next()? {
+                    'n' => parsed.push('\n'),
+                    't' => parsed.push('\t'),
+                    other => parsed.push(other),
+                },
+                _ => parsed.push(c),
+            }
+        }
+        return None;
+    }
+    let value = value.split(" #").next().unwrap_or_default();
+    Some(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+
+    safe_test!(test_parse_dotenv, {
+        let content = "# comment\n\
+            export API_URL=https://example.com # the api\n\
+            EMPTY=\n\
+            SINGLE='a # b'\n\
+            DOUBLE=\"line\\nbreak \\\"quoted\\\"\"\n\
+            API_URL=https://override.example.com\n";
+        let vars = parse(content)?;
+        assert_eq!(
+            vars,
+            vec![
+                ("API_URL".to_string(), "https://override.example.com".to_string()),
+                ("EMPTY".to_string(), String::new()),
+                ("SINGLE".to_string(), "a # b".to_string()),
+                ("DOUBLE".to_string(), "line\nbreak \"quoted\"".to_string()),
+            ]
+        );
+
+        assert!(parse("NOT AN ASSIGNMENT").unwrap_err().to_string().contains("Line 1"));
+        assert!(parse("\n1BAD=x").unwrap_err().to_string().contains("Line 2"));
+        assert!(parse("OPEN=\"never closed").is_err());
+
+        Ok(())
+    });
+}
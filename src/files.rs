@@ -0,0 +1,435 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+use tracing::{debug, warn};
+
+use crate::models::Config;
+use crate::rp_macros::{base_output_dir, input_dir, repo_dir};
+
+/// Name of the lock file created in a project directory while rpcfg changes its outputs.
+pub const LOCK_FILE_NAME: &str = ".rpcfg.lock";
+
+/// How long to wait for another rpcfg process to release a project lock by default.
+/// Override with the `RPCFG_LOCK_TIMEOUT` environment variable (seconds).
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Permissions for the directories rpcfg creates on Unix: owner only.
+pub const PRIVATE_DIR_MODE: u32 = 0o700;
+
+/// Permissions for the output and snapshot files rpcfg writes on Unix: owner read/write only.
+pub const PRIVATE_FILE_MODE: u32 = 0o600;
+
+/// Start of the line at the top of an ENV output that ties it to the JSON output saved with it.
+pub const PAIR_STAMP_PREFIX: &str = "# rpcfg-pair: ";
+
+#[cfg(test)]
+thread_local! {
+    /// Makes `write_all_or_none` stop after this many renames, as if the process had crashed.
+    pub(crate) static CRASH_AFTER_RENAMES: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+/// Writes a file atomically by writing a temporary file in the same directory and renaming it.
+///
+/// Readers see either the old contents or the new ones, never a partial write. An existing
+/// file keeps its permissions; a new file gets the temporary file's (owner read/write only).
+///
+/// # Errors
+///
+/// This function will return an error if the temporary file cannot be written or renamed.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let staged = stage(path, contents, false)?;
+    staged
+        .persist(path)
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Writes several files, putting back the ones already replaced if a later one cannot be.
+///
+/// The files hold configuration values, so they are always written with `PRIVATE_FILE_MODE`.
+/// Every file is first written to a temporary file next to its target, so nothing is replaced
+/// unless all of them could be written. The temporary files are then renamed over the targets
+/// in order; if a rename fails, the targets already replaced are atomically restored to their
+/// previous contents (or removed if they did not exist).
+///
+/// Each file is replaced atomically, but the set is not: a crash between two renames leaves
+/// the earlier files with the new contents and the later ones with the old. The JSON and ENV
+/// outputs are stamped (see `stamp_env`) so that `check_pair` can tell when that happened.
+///
+/// # Errors
+///
+/// This function will return an error if any file cannot be staged or renamed. In that case
+/// the targets are left as they were, unless restoring one of them fails too.
+pub fn write_all_or_none(files: &[(PathBuf, String)]) -> Result<()> {
+    let mut staged = Vec::new();
+    for (path, contents) in files {
+        let previous = fs::read_to_string(path).ok();
+        staged.push((path, stage(path, contents, true)?, previous));
+    }
+
+    let mut replaced: Vec<(&PathBuf, Option<String>)> = Vec::new();
+    for (path, temp, previous) in staged {
+        #[cfg(test)]
+        if CRASH_AFTER_RENAMES.with(|crash| crash.get()) == Some(replaced.len()) {
+            anyhow::bail!("Simulated crash before writing {}", path.display());
+        }
+        if let Err(e) = temp.persist(path) {
+            warn!("Failed to replace {}, restoring the files already written", path.display());
+            for (path, previous) in replaced {
+                let restored = match previous {
+                    Some(contents) => stage(path, &contents, true)
+                        .and_then(|temp| temp.persist(path).map(|_| ()).map_err(|e| e.error.into())),
+                    None => fs::remove_file(path).map_err(Into::into),
+                };
+                if let Err(restore_error) = restored {
+                    warn!("Failed to restore {}: {}", path.display(), restore_error);
+                }
+            }
+            return Err(e.error).with_context(|| format!("Failed to write file: {}", path.display()));
+        }
+        replaced.push((path, previous));
+    }
+    Ok(())
+}
+
+/// Returns a 64-bit FNV-1a hash of the text in hex, which unlike the standard library's
+/// hasher is the same across Rust versions, so it can be written to disk.
+pub fn stable_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Returns the stamp of a JSON output: the `stable_hash` of its contents.
+pub fn pair_stamp(json_content: &str) -> String {
+    stable_hash(json_content)
+}
+
+/// Puts the stamp of the JSON output saved with an ENV output on the ENV output's first line,
+/// replacing any stamp it already has. The line is a comment, so shells and `.env` loaders
+/// skip it.
+pub fn stamp_env(json_content: &str, env_content: &str) -> String {
+    let unstamped = match env_content.strip_prefix(PAIR_STAMP_PREFIX) {
+        Some(rest) => rest.split_once('\n').map_or("", |(_, rest)| rest),
+        None => env_content,
+    };
+    format!("{}{}\n{}", PAIR_STAMP_PREFIX, pair_stamp(json_content), unstamped)
+}
+
+/// Checks that a JSON output and the ENV output next to it were written by the same save.
+///
+/// Passes if either file is missing or the ENV output has no stamp (it was written before
+/// stamps were added).
+///
+/// # Errors
+///
+/// This function will return an error if the stamp in the ENV output does not match the
+/// JSON output, which means a save was interrupted between writing the two.
+pub fn check_pair(json_path: &Path, env_path: &Path) -> Result<()> {
+    let (Ok(json_content), Ok(env_content)) =
+        (fs::read_to_string(json_path), fs::read_to_string(env_path))
+    else {
+        return Ok(());
+    };
+    let Some(stamp) = env_content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(PAIR_STAMP_PREFIX))
+    else {
+        return Ok(());
+    };
+    if stamp.trim() != pair_stamp(&json_content) {
+        anyhow::bail!(
+            "{} and {} were written by different saves (a save was interrupted); run `rpcfg collect` to save them again or `rpcfg rollback` to restore a snapshot",
+            json_path.display(),
+            env_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Writes the contents to a temporary file next to `path`, ready to be renamed over it.
+///
+/// A private file gets `PRIVATE_FILE_MODE`; otherwise an existing target's permissions are kept.
+fn stage(path: &Path, contents: &str, private: bool) -> Result<NamedTempFile> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut temp = NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create a temporary file in {}", dir.display()))?;
+    temp.write_all(contents.as_bytes())?;
+    temp.as_file().sync_all()?;
+    if private {
+        set_mode(temp.path(), PRIVATE_FILE_MODE)?;
+    } else if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp.path(), metadata.permissions())?;
+    }
+    Ok(temp)
+}
+
+/// Creates a directory and any missing parents, giving the new ones `PRIVATE_DIR_MODE`.
+///
+/// Directories that already exist keep their permissions; `rpcfg doctor --fix` tightens them.
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, PRIVATE_DIR_MODE);
+    builder
+        .create(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))
+}
+
+/// Moves a file or directory, copying and then removing it when it cannot be renamed
+/// (for example across file systems).
+pub fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    debug!("Cannot rename {}, copying it instead", from.display());
+    copy_recursive(from, to)
+        .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)?;
+    } else {
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        fs::set_permissions(to, fs::metadata(from)?.permissions())?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Returns the permission bits of a path if they give access to anyone but the owner.
+///
+/// Always returns None on platforms without Unix permissions.
+pub fn loose_permissions(path: &Path) -> Result<Option<u32>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .with_context(|| format!("Failed to read permissions: {}", path.display()))?
+            .permissions()
+            .mode()
+            & 0o777;
+        if mode & 0o077 != 0 {
+            return Ok(Some(mode));
+        }
+    }
+    Ok(None)
+}
+
+/// Restricts a file to `PRIVATE_FILE_MODE` or a directory to `PRIVATE_DIR_MODE`.
+pub fn make_private(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        set_mode(path, PRIVATE_DIR_MODE)
+    } else {
+        set_mode(path, PRIVATE_FILE_MODE)
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions: {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// An advisory lock on a project directory, released when dropped.
+///
+/// The lock is an OS file lock (`flock` on Unix, `LockFileEx` on Windows) on a file in the
+/// directory, held on an open file for as long as the `ProjectLock` lives. The operating
+/// system releases it when the file is closed, so a crashed process never leaves a stale lock
+/// behind and a live one is never mistaken for it. The lock file itself stays in place.
+#[derive(Debug)]
+pub struct ProjectLock {
+    path: PathBuf,
+    _file: fs::File,
+}
+
+impl ProjectLock {
+    /// Locks the directory, waiting up to the default timeout for another process to release it.
+    pub fn acquire(dir: &Path) -> Result<ProjectLock> {
+        let timeout = std::env::var("RPCFG_LOCK_TIMEOUT")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT);
+        Self::acquire_with_timeout(dir, timeout)
+    }
+
+    /// Locks the directory, waiting up to `timeout` for another process to release it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory or lock file cannot be created, or
+    /// the lock is still held when the timeout expires.
+    pub fn acquire_with_timeout(dir: &Path, timeout: Duration) -> Result<ProjectLock> {
+        create_private_dir(dir)?;
+        let path = dir.join(LOCK_FILE_NAME);
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file: {}", path.display()))?;
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    debug!("Acquired lock {}", path.display());
+                    return Ok(ProjectLock { path, _file: file });
+                }
+                Err(fs::TryLockError::WouldBlock) => {
+                    if started.elapsed() >= timeout {
+                        anyhow::bail!(
+                            "Another rpcfg process is using {} (waited {}s; set RPCFG_LOCK_TIMEOUT to wait longer)",
+                            dir.display(),
+                            timeout.as_secs()
+                        );
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(fs::TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Failed to lock {}", path.display()))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock
+        debug!("Released lock {}", self.path.display());
+    }
+}
+
+/// Locks the project directory that holds a configuration's outputs.
+///
+/// Commands that change the JSON and ENV outputs hold this lock so that concurrent rpcfg
+/// runs sharing a home directory take turns.
+pub fn lock_project(config: &Config) -> Result<ProjectLock> {
+    let project_dir = base_output_dir(config)
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get project directory"))?;
+    ProjectLock::acquire(&project_dir)
+}
+
+/// Returns the `.gitignore` next to the input file and the entry that keeps the
+/// `stored: "repo"` output directory out of git.
+fn gitignore_entry(config: &Config) -> Result<(PathBuf, String)> {
+    let dir = input_dir(config)
+        .ok_or_else(|| anyhow::anyhow!("Storage type repo needs the path of the input file"))?;
+    Ok((
+        dir.join(".gitignore"),
+        format!("/{}/", repo_dir(config)?.trim_matches('/')),
+    ))
+}
+
+/// Returns true if the `stored: "repo"` output directory is listed in the `.gitignore`
+/// next to the input file.
+pub fn is_gitignored(config: &Config) -> bool {
+    let Ok((path, entry)) = gitignore_entry(config) else {
+        return false;
+    };
+    fs::read_to_string(path).unwrap_or_default().lines().any(|line| {
+        line.trim().trim_matches('/') == entry.trim_matches('/')
+    })
+}
+
+/// Makes sure the `stored: "repo"` output directory is listed in the `.gitignore` next to the
+/// input file, so the values are never committed.
+///
+/// # Returns
+///
+/// * `Result<bool>` - True if the entry was added, false if it was already there.
+pub fn ensure_gitignored(config: &Config) -> Result<bool> {
+    if is_gitignored(config) {
+        return Ok(false);
+    }
+    let (path, entry) = gitignore_entry(config)?;
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to update {}", path.display()))?;
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", entry)?;
+    debug!("Added {} to {}", entry, path.display());
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use tempfile::TempDir;
+
+    safe_test!(test_write_all_or_none_restores_on_failure, {
+        let dir = TempDir::new()?;
+        let json_path = dir.path().join("config.json");
+        let env_path = dir.path().join("config.env");
+
+        write_all_or_none(&[
+            (json_path.clone(), "old json".to_string()),
+            (env_path.clone(), "old env".to_string()),
+        ])?;
+        assert_eq!(fs::read_to_string(&json_path)?, "old json");
+
+        // A directory in place of the second target makes its rename fail
+        let blocked = dir.path().join("blocked.env");
+        fs::create_dir(&blocked)?;
+        fs::write(blocked.join("keep"), "")?;
+        let result = write_all_or_none(&[
+            (json_path.clone(), "new json".to_string()),
+            (blocked, "new env".to_string()),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&json_path)?, "old json");
+
+        Ok(())
+    });
+
+    safe_test!(test_project_lock, {
+        let dir = TempDir::new()?;
+        {
+            let _lock = ProjectLock::acquire_with_timeout(dir.path(), Duration::from_millis(10))?;
+            let second = ProjectLock::acquire_with_timeout(dir.path(), Duration::from_millis(200));
+            assert!(second.unwrap_err().to_string().contains("Another rpcfg process"));
+        }
+        // Dropping the lock releases it; the file stays for the next holder
+        assert!(dir.path().join(LOCK_FILE_NAME).exists());
+        let _lock = ProjectLock::acquire_with_timeout(dir.path(), Duration::from_millis(10))?;
+
+        Ok(())
+    });
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::dotenv;
+use crate::models::{Config, ConfigItem, ItemType};
+
+/// Parts of a name that mark a value as secret, matched against the uppercased name.
+const SECRET_MARKERS: &[&str] = &[
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "TOKEN",
+    "API_KEY",
+    "APIKEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+    "CONNECTION_STRING",
+];
+
+/// Returns true if `key` is the key of one of the default rpcfg items, which are not app
+/// settings when found in a saved output.
+fn is_rpcfg_key(key: &str) -> bool {
+    Config::default().rpcfg.iter().any(|item| item.key == key)
+}
+
+/// Returns true if a variable or key name suggests that its value is a secret.
+pub fn looks_secret(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// Infers an app item from an environment variable.
+///
+/// The key is the lowercased name. The value becomes the default and decides the type,
+/// unless the name looks secret: secret values are never written to the schema.
+pub fn item_from_var(name: &str, value: &str) -> ConfigItem {
+    let secret = looks_secret(name);
+    ConfigItem {
+        key: name.to_lowercase(),
+        description: name.to_string(),
+        default: if secret { String::new() } else { value.to_string() },
+        temp_environment_variable_name: name.to_string(),
+        required_as_env: true,
+        secret,
+        item_type: ItemType::guess(value),
+        value: value.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Values found in an existing file, for generating a schema.
+#[derive(Debug, Clone, Default)]
+pub struct Inferred {
+    /// The inferred app items, in file order.
+    pub items: Vec<ConfigItem>,
+    /// rpcfg values found in the file, such as `project_name` in a saved output.
+    pub rpcfg: Vec<(String, String)>,
+}
+
+/// Infers app items from a `.env` file, or a JSON file if the path ends in `.json`.
+///
+/// A JSON file is an object of settings, such as a saved output; nested objects are flattened
+/// with `_` between the keys. Its rpcfg keys are returned separately.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed.
+pub fn infer_from_file(path: &str) -> Result<Inferred> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read file: {}", path))?;
+    if path.ends_with(".json") {
+        let value: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON from file: {}", path))?;
+        let Value::Object(map) = value else {
+            anyhow::bail!("Expected a JSON object of settings in {}", path);
+        };
+        let mut flat = Vec::new();
+        flatten("", &map, &mut flat);
+        Ok(infer_from_pairs(flat, false))
+    } else {
+        let vars = dotenv::parse(&content).with_context(|| format!("Failed to parse file: {}", path))?;
+        Ok(infer_from_pairs(vars, true))
+    }
+}
+
+fn infer_from_pairs(pairs: Vec<(String, String)>, from_env: bool) -> Inferred {
+    let mut inferred = Inferred::default();
+    for (key, value) in pairs {
+        // The `.env` rpcfg writes has the keys uppercased
+        let lowercase = key.to_lowercase();
+        if is_rpcfg_key(&lowercase) {
+            inferred.rpcfg.push((lowercase, value));
+            continue;
+        }
+        let mut item = item_from_var(&key, &value);
+        if !from_env {
+            // JSON keys are used as they are; the variable name is derived when it can be one
+            item.key = key.clone();
+            item.description = key.clone();
+            let name = key.to_uppercase();
+            item.temp_environment_variable_name = if dotenv::is_valid_name(&name) { name } else { String::new() };
+            item.required_as_env = false;
+        }
+        inferred.items.push(item);
+    }
+    inferred
+}
+
+fn flatten(prefix: &str, map: &serde_json::Map<String, Value>, out: &mut Vec<(String, String)>) {
+    for (key, value) in map {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}_{}", prefix, key)
+        };
+        match value {
+            Value::Object(nested) => flatten(&key, nested, out),
+            Value::String(s) => out.push((key, s.clone())),
+            Value::Null => out.push((key, String::new())),
+            other => out.push((key, other.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use tempfile::TempDir;
+
+    safe_test!(test_infer_items, {
+        let dir = TempDir::new()?;
+        let env_path = dir.path().join(".env");
+        std::fs::write(
+            &env_path,
+            "STORED=local\nPROJECT_NAME=shop\nAPI_URL=https://example.com\nDB_PASSWORD=hunter2\nWORKERS=4\nDEBUG=false\nRATIO=inf\n",
+        )?;
+        let inferred = infer_from_file(env_path.to_str().unwrap())?;
+        let types: Vec<ItemType> = inferred.items.iter().map(|item| item.item_type).collect();
+        assert_eq!(
+            types,
+            vec![ItemType::Url, ItemType::String, ItemType::Integer, ItemType::Boolean, ItemType::String]
+        );
+        assert!(inferred.rpcfg.contains(&("project_name".to_string(), "shop".to_string())));
+        assert!(ItemType::Number.check("NaN").is_err());
+        let password = &inferred.items[1];
+        assert_eq!(password.key, "db_password");
+        assert!(password.secret);
+        assert_eq!(password.default, "");
+        assert_eq!(inferred.items[0].temp_environment_variable_name, "API_URL");
+
+        let json_path = dir.path().join("saved.json");
+        std::fs::write(
+            &json_path,
+            r#"{"project_name": "shop", "environment": "prod", "db": {"host": "db.local", "port": 5432}}"#,
+        )?;
+        let inferred = infer_from_file(json_path.to_str().unwrap())?;
+        let keys: Vec<&str> = inferred.items.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, vec!["db_host", "db_port"]);
+        assert_eq!(inferred.items[1].item_type, ItemType::Integer);
+        assert!(inferred.rpcfg.contains(&("project_name".to_string(), "shop".to_string())));
+
+        Ok(())
+    });
+}
@@ -0,0 +1,105 @@
+//! Manages repository configurations: the schema files, the values collected for them, and
+//! the outputs and code generated from them. The `rpcfg` binary is the command line front end.
+
+ pub mod commands{
+    pub mod codegen;
+    pub mod collect;
+    pub mod copy;
+    pub mod delete;
+    pub mod diff;
+    pub mod doctor;
+    pub mod fetch;
+    pub mod history;
+    pub mod import;
+    pub mod init;
+    pub mod list;
+    pub mod migrate;
+    pub mod relocate;
+    pub mod rollback;
+    pub mod schema;
+    pub mod show;
+    pub mod template;
+ }
+pub mod codegen;
+pub mod common;
+pub mod compose;
+pub mod dotenv;
+pub mod files;
+pub mod infer;
+pub mod migrate;
+pub mod models;
+pub mod resolve;
+pub mod rp_macros;
+pub mod snapshots;
+pub mod templates;
+#[cfg(test)]
+mod test_utils;
+pub mod tui;
+pub mod undo;
+
+// Re-export important structs and macros - this will remove the heirarchy and put them at the crate level
+pub use common::*;
+pub use models::*;
+pub use rp_macros::*;
+#[cfg(test)]
+pub(crate) use test_utils::*;
+
+use anyhow::Result;
+
+/// Parses a JSON configuration file into a Config struct.
+///
+/// This function reads a JSON file from the given path and deserializes it into a Config struct.
+/// Files named by `extends` and `include` are merged in (see `compose::load_composed`).
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice that holds the path to the JSON file
+///
+/// # Returns
+///
+/// * `Result<Config>` - The parsed Config struct or an error
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The file cannot be opened
+/// * The JSON in the file cannot be parsed into a Config struct
+/// * A file named by `extends` or `include` cannot be loaded, or the files form a cycle
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+/// use tempfile::NamedTempFile;
+///
+/// // Create a temporary JSON file
+/// let mut temp_file = NamedTempFile::new().unwrap();
+/// writeln!(temp_file, r#"{{
+///     "format_version": 1,
+///     "rpcfg": [
+///         {{ "key": "project_name", "description": "Name of the project", "default": "test_project" }}
+///     ],
+///     "app": [
+///         {{ "key": "api_url", "description": "Base URL of the API", "default": "https://example.com", "type": "url" }}
+///     ]
+/// }}"#).unwrap();
+///
+/// // Parse the config file; values start out as the defaults
+/// let config = rpcfg::parse_config_file(temp_file.path().to_str().unwrap()).unwrap();
+/// assert_eq!(config.get_settings("project_name")[0].value, "test_project");
+/// assert_eq!(config.get_settings("stored")[0].value, "local");
+/// ```
+pub fn parse_config_file(file_path: &str) -> Result<Config> {
+    let mut config = compose::load_composed(file_path)?;
+    // Update config items with default values
+    for item in config.rpcfg.iter_mut().chain(config.app.iter_mut()) {
+        if item.value.is_empty() {
+            item.value = item.default.clone();
+        }
+    }
+    // validate the rpcfg items
+    config.validate_rpcfg_config()?;
+
+    config.input_file = file_path.to_string();
+    Ok(config)
+}
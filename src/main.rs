@@ -2,34 +2,18 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
- mod commands{
-    pub mod collect;
-    pub mod delete;
-    pub mod fetch;
-    pub mod init;
- }
-pub mod common;
-pub mod models;
-pub mod rp_macros;
-pub mod test_utils;
-
-// Re-export important structs and macros - this will remove the heirarchy and put them at the crate level
-pub use common::*;
-pub use models::*;
-pub use rp_macros::*;
-pub use test_utils::*;
-
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use crate::commands::collect::execute;
-use crate::test_utils::create_test_config;
-use commands::{collect, init, fetch};
-use commands::delete;
+use rpcfg::commands::collect::execute;
+use rpcfg::{commands, dotenv, parse_config_file, resolve, rp_macros, templates, Config, Status};
+use rpcfg::commands::{codegen, collect, copy, diff, doctor, import, init, fetch, history, list, relocate, rollback, schema, show, template};
+use rpcfg::commands::delete;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{stderr, stdin, stdout, BufReader, Write};
+use std::path::PathBuf;
 use tabwriter::TabWriter;
 use tracing::{debug, info, trace, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -50,6 +34,22 @@ struct Cli {
     /// Set the tracing level (off, error, warn, info, debug, trace)
     #[arg(long, global = true, default_value = "error")]
     trace_level: Level,
+
+    /// Override a setting for this run (KEY=VALUE, may be repeated)
+    #[arg(long = "set", global = true, value_parser = parse_key_value)]
+    set: Vec<(String, String)>,
+
+    /// Directory holding the outputs, snapshots and templates (overrides RPCFG_HOME and the XDG defaults)
+    #[arg(long, global = true)]
+    root: Option<PathBuf>,
+}
+
+/// Parses a `KEY=VALUE` command line argument.
+fn parse_key_value(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", arg))
 }
 
 #[derive(Subcommand)]
@@ -59,6 +59,30 @@ enum Commands {
         /// Path to the output JSON file
         #[arg(short = 'o', long = "output")]
         output: String,
+
+        /// Start from the app items of a template (see `rpcfg template list`)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Infer the app items from a .env file or a JSON file (ending in .json)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Default for the `project_name` item
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Default for the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+
+        /// Overwrite the file if it exists
+        #[arg(long, conflicts_with = "merge")]
+        force: bool,
+
+        /// Add the missing items to an existing file instead of replacing it
+        #[arg(long)]
+        merge: bool,
     },
     /// Collect repository configurations and generate output files
     Collect {
@@ -69,6 +93,14 @@ enum Commands {
         /// Ignore timestamp checks and always collect
         #[arg(long = "ignore-timestamps")]
         ignore_timestamps: bool,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+
+        /// Use the full-screen terminal UI (falls back to the line interface when not on a terminal)
+        #[arg(long)]
+        tui: bool,
     },
     /// Delete generated output files
     Delete {
@@ -79,79 +111,254 @@ enum Commands {
         /// Skip confirmation prompt
         #[arg(long)]
         no_prompt: bool,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
     },
     /// Return the JSON config with the values
     Fetch {
         /// Path to the input JSON file
         #[arg(short = 'i', long = "input")]
         input_file: String,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
     },
     /// Show the configuration table
     Show {
         /// Path to the input JSON file
         #[arg(short = 'i', long = "input")]
         input_file: String,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+    },
+    /// List saved snapshots of the output files with their changes
+    History {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+    },
+    /// Restore the output files from a saved snapshot
+    Rollback {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Id of the snapshot to restore, as listed by `history`
+        #[arg(long = "to")]
+        to: String,
+
+        /// Skip confirmation prompt
+        #[arg(long)]
+        no_prompt: bool,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+    },
+    /// Show where rpcfg reads and writes, and check the setup for problems
+    Doctor {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Repair the problems that can be fixed automatically
+        #[arg(long)]
+        fix: bool,
+
+        /// Write the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+    },
+    /// Copy saved values to another environment, config name or project
+    Copy {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Environment to copy from
+        #[arg(long = "from-env")]
+        from_env: String,
+
+        /// Environment to copy to (defaults to --from-env)
+        #[arg(long = "to-env")]
+        to_env: Option<String>,
+
+        /// Config name to copy to, overriding the `config_name` item
+        #[arg(long = "to-config")]
+        to_config: Option<String>,
+
+        /// Project to copy to, overriding the `project_name` item
+        #[arg(long = "to-project")]
+        to_project: Option<String>,
+
+        /// Leave out the values of items marked as secret
+        #[arg(long)]
+        exclude_secrets: bool,
+
+        /// Leave out the value of this key (may be repeated)
+        #[arg(long = "exclude")]
+        exclude: Vec<String>,
+
+        /// Do not prompt for the environment-specific values after copying
+        #[arg(long)]
+        no_collect: bool,
+    },
+    /// Show the differences between two environments or two saved outputs
+    Diff {
+        /// Path to the input JSON file (required with --env; with --file, used to mask secrets)
+        #[arg(short = 'i', long = "input")]
+        input_file: Option<String>,
+
+        /// Environment to compare; give exactly two
+        #[arg(long = "env")]
+        env: Vec<String>,
+
+        /// Saved JSON output to compare; give exactly two
+        #[arg(long = "file", conflicts_with = "env")]
+        file: Vec<String>,
+
+        /// Write the differences as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Exit with status 1 if there are differences
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Import values from a .env file or the environment
+    Import {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Path to the .env file to import
+        #[arg(long, conflicts_with = "from_env", required_unless_present = "from_env")]
+        from: Option<String>,
+
+        /// Import from the environment of this process
+        #[arg(long)]
+        from_env: bool,
+
+        /// Only consider variables whose names start with this prefix
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Create app items for variables that match no item
+        #[arg(long)]
+        create: bool,
+
+        /// Environment to use, overriding the `environment` item
+        #[arg(long = "env")]
+        env: Option<String>,
+    },
+    /// List the saved configurations under the storage root
+    List {
+        /// Only list configurations of this project
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only list configurations for this environment
+        #[arg(long = "env")]
+        env: Option<String>,
+
+        /// Write the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move saved outputs and snapshots from the legacy ~/.rpcfg to the storage root
+    Relocate {
+        /// Directory to move everything to (defaults to the storage root and config directory from --root, RPCFG_HOME or XDG)
+        #[arg(long = "to")]
+        to: Option<PathBuf>,
+
+        /// Skip confirmation prompt
+        #[arg(long)]
+        no_prompt: bool,
+    },
+    /// Upgrade an input file to the current format version, keeping a backup
+    Migrate {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// List the changes without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate typed code for reading the settings of a schema
+    Codegen {
+        /// Path to the input JSON file
+        #[arg(short = 'i', long = "input")]
+        input_file: String,
+
+        /// Language to generate: rust, typescript or python
+        #[arg(long)]
+        lang: rpcfg::codegen::Lang,
+
+        /// Write the code to this file instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+
+        /// Name of the generated type (defaults to the config name in PascalCase)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Exit with status 1 if the file given with -o is not up to date, instead of writing it
+        #[arg(long)]
+        check: bool,
+    },
+    /// Emit the JSON Schema of the input file format
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+    /// List, show and add the schema templates used by `init --template`
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
     },
 }
 
-/// Parses a JSON configuration file into a Config struct.
-///
-/// This function reads a JSON file from the given path and deserializes it into a Config struct.
-///
-/// # Arguments
-///
-/// * `file_path` - A string slice that holds the path to the JSON file
-///
-/// # Returns
-///
-/// * `Result<Config>` - The parsed Config struct or an error
-///
-/// # Errors
-///
-/// This function will return an error if:
-/// * The file cannot be opened
-/// * The JSON in the file cannot be parsed into a Config struct
-///
-/// # Example
-///
-/// ```
-/// use rpcfg::models::{Config, ConfigItem};
-/// use std::fs::File;
-/// use std::io::Write;
-/// use tempfile::NamedTempFile;
-///
-/// // Create a temporary JSON file
-/// let mut temp_file = NamedTempFile::new().unwrap();
-/// writeln!(temp_file, r#"{{
-///     "stored": "local",
-///     "config_version": "1.0",
-///     "project_name": "test_project",
-///     "config_name": "test_config",
-///     "is_test": true,
-///     "items": []
-/// }}"#).unwrap();
-///
-/// // Parse the config file
-/// let config = rpcfg::parse_config_file(temp_file.path().to_str().unwrap());
-/// assert!(config.is_ok());
-/// ```
-fn parse_config_file(file_path: &str) -> Result<Config> {
-    let file =
-        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
-    let reader = BufReader::new(file);
-    let mut config: Config = serde_json::from_reader(reader)
-        .with_context(|| format!("Failed to parse JSON from file: {}", file_path))?;
-    // Update config items with default values
-    for item in config.rpcfg.iter_mut().chain(config.app.iter_mut()) {
-        if item.value.is_empty() {
-            item.value = item.default.clone();
-        }
-    }
-    // validate the rpcfg items
-    config.validate_rpcfg_config()?;
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// List the built-in and user templates
+    List,
+    /// Write a template as JSON
+    Show {
+        /// Name of the template
+        name: String,
+    },
+    /// Save the app items of a schema file as a user template
+    Add {
+        /// Name of the new template
+        name: String,
 
-    config.input_file = file_path.to_string();
-    Ok(config)
+        /// Schema file to take the app items from
+        #[arg(long)]
+        from: String,
+
+        /// Description of the template
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Replace a user template with the same name
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 /// The main entry point for the CLI application.
@@ -207,41 +414,301 @@ fn main() -> Result<()> {
 
     // Execute the appropriate command
     match &cli.command {
-        Commands::Init { output } => {
+        Commands::Init {
+            output,
+            template,
+            from,
+            project,
+            env,
+            force,
+            merge,
+        } => {
             info!("Executing Init command");
-            let result = init::execute(output, &mut stdin_reader, &mut stdout)?;
+            let template = match template {
+                Some(name) => Some(templates::find(&rp_macros::resolve_config_dir(cli.root.as_deref(), true)?, name)?),
+                None => None,
+            };
+            let options = init::InitOptions {
+                template,
+                from: from.clone(),
+                project: project.clone(),
+                env: env.clone(),
+                force: *force,
+                merge: *merge,
+                prompt: !cli.silent,
+            };
+            let result = init::execute(output, &options, &mut stdin_reader, &mut stdout)?;
             println!("{}", result.message);
         }
         Commands::Collect {
             input_file,
             ignore_timestamps,
+            env,
+            tui,
         } => {
             info!("Executing Collect command");
-            let mut config = get_config(input_file)?;
+            let mut config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
             collect::execute(
                 &mut config,
                 input_file,
                 *ignore_timestamps,
+                *tui && !cli.silent,
                 &mut stdin_reader,
                 &mut stdout,
             )?;
         }
-        Commands::Delete { input_file, no_prompt } => {
+        Commands::Delete { input_file, no_prompt, env } => {
             info!("Executing Delete command");
-            let config = get_config(input_file)?;
+            let config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
             let result = delete::execute(&config, *no_prompt, &mut stdin_reader, &mut stdout)?;
             println!("{}", result.message);
         }
-        Commands::Fetch { input_file } => {
+        Commands::Fetch { input_file, env } => {
             info!("Executing Fetch command");
-            let config = get_config(input_file)?;
+            let config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
             let result = fetch::execute(&config, &mut stdin_reader, &mut stdout)?;
             debug!("Fetch command result: {:?}", result);
         }
-        Commands::Show { input_file } => {
+        Commands::Show { input_file, env } => {
             info!("Executing Show command");
-            let config = get_config(input_file)?;
-            // TODO: Implement Show command
+            let config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
+            let result = show::execute(&config, &mut stdin_reader, &mut stdout)?;
+            debug!("Show command result: {:?}", result);
+        }
+        Commands::History { input_file, env } => {
+            info!("Executing History command");
+            let config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
+            let result = history::execute(&config, &mut stdin_reader, &mut stdout)?;
+            debug!("History command result: {:?}", result);
+        }
+        Commands::Rollback {
+            input_file,
+            to,
+            no_prompt,
+            env,
+        } => {
+            info!("Executing Rollback command");
+            let config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
+            let result = rollback::execute(&config, to, *no_prompt, &mut stdin_reader, &mut stdout)?;
+            println!("{}", result.message);
+        }
+        Commands::Doctor {
+            input_file,
+            fix,
+            json,
+            env,
+        } => {
+            info!("Executing Doctor command");
+            let config = doctor::load(input_file, &overrides(&cli, env), cli.root.as_deref());
+            let result = doctor::execute(input_file, config, *fix, *json, &mut stdin_reader, &mut stdout)?;
+            if matches!(result.status, Status::Error) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Copy {
+            input_file,
+            from_env,
+            to_env,
+            to_config,
+            to_project,
+            exclude_secrets,
+            exclude,
+            no_collect,
+        } => {
+            info!("Executing Copy command");
+            let root = cli.root.as_deref();
+            let source = get_config(input_file, &overrides(&cli, &Some(from_env.clone())), root)?;
+            let to_env = to_env.clone().unwrap_or_else(|| from_env.clone());
+            let mut target_overrides = overrides(&cli, &Some(to_env));
+            if let Some(config_name) = to_config {
+                target_overrides.push(("config_name".to_string(), config_name.clone()));
+            }
+            if let Some(project_name) = to_project {
+                target_overrides.push(("project_name".to_string(), project_name.clone()));
+            }
+            let mut target = get_config(input_file, &target_overrides, root)?;
+            let options = copy::CopyOptions {
+                exclude_secrets: *exclude_secrets,
+                exclude: exclude.clone(),
+            };
+            let result = copy::execute(
+                &source,
+                &mut target,
+                &options,
+                !*no_collect && !cli.silent,
+                &mut stdin_reader,
+                &mut stdout,
+            )?;
+            println!("{}", result.message);
+        }
+        Commands::Diff {
+            input_file,
+            env,
+            file,
+            json,
+            exit_code,
+        } => {
+            info!("Executing Diff command");
+            let root = cli.root.as_deref();
+            let (left, right, schema) = match (input_file, env.as_slice(), file.as_slice()) {
+                (Some(input_file), [left, right], []) => {
+                    let left = get_config(input_file, &overrides(&cli, &Some(left.clone())), root)?;
+                    let right = get_config(input_file, &overrides(&cli, &Some(right.clone())), root)?;
+                    (
+                        diff::Side::from_config(&left)?,
+                        diff::Side::from_config(&right)?,
+                        Some(right),
+                    )
+                }
+                (_, [], [left, right]) => {
+                    let schema = match input_file {
+                        Some(input_file) => Some(get_config(input_file, &overrides(&cli, &None), root)?),
+                        None => None,
+                    };
+                    (diff::Side::from_file(left)?, diff::Side::from_file(right)?, schema)
+                }
+                _ => anyhow::bail!(
+                    "Compare two environments with -i <FILE> --env <A> --env <B>, \
+                     or two saved outputs with --file <A> --file <B>"
+                ),
+            };
+            let result = diff::execute(
+                &left,
+                &right,
+                schema.as_ref(),
+                *json,
+                &mut stdin_reader,
+                &mut stdout,
+            )?;
+            if *exit_code && matches!(result.status, Status::Error) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Import {
+            input_file,
+            from,
+            from_env,
+            prefix,
+            create,
+            env,
+        } => {
+            info!("Executing Import command");
+            let mut config = get_config(input_file, &overrides(&cli, env), cli.root.as_deref())?;
+            let (mut vars, source) = match from {
+                Some(path) => {
+                    let content = std::fs::read_to_string(path)
+                        .with_context(|| format!("Failed to read file: {}", path))?;
+                    let vars = dotenv::parse(&content)
+                        .with_context(|| format!("Failed to parse file: {}", path))?;
+                    (vars, path.clone())
+                }
+                None => (std::env::vars().collect::<Vec<_>>(), "the environment".to_string()),
+            };
+            if let Some(prefix) = prefix {
+                vars.retain(|(name, _)| name.starts_with(prefix.as_str()));
+            }
+            if *from_env && *create && prefix.is_none() {
+                anyhow::bail!("--create with --from-env needs --prefix to select the variables");
+            }
+            let options = import::ImportOptions {
+                create: *create,
+                report_unmatched: !*from_env || prefix.is_some(),
+            };
+            let result = import::execute(
+                &mut config,
+                &vars,
+                &source,
+                &options,
+                &mut stdin_reader,
+                &mut stdout,
+            )?;
+            debug!("Import command result: {:?}", result);
+        }
+        Commands::List { project, env, json } => {
+            info!("Executing List command");
+            let root = rp_macros::resolve_root(cli.root.as_deref(), true)?;
+            let result = list::execute(
+                &root,
+                project.as_deref(),
+                env.as_deref(),
+                *json,
+                &mut stdin_reader,
+                &mut stdout,
+            )?;
+            debug!("List command result: {:?}", result);
+        }
+        Commands::Relocate { to, no_prompt } => {
+            info!("Executing Relocate command");
+            let from = rp_macros::legacy_root()
+                .ok_or_else(|| anyhow::anyhow!("Cannot find the home directory"))?;
+            let (to, config_to) = match to {
+                Some(to) => (to.clone(), to.clone()),
+                None => (
+                    rp_macros::resolve_root(cli.root.as_deref(), false)?,
+                    rp_macros::resolve_config_dir(cli.root.as_deref(), false)?,
+                ),
+            };
+            let result = relocate::execute(&from, &to, &config_to, *no_prompt, &mut stdin_reader, &mut stdout)?;
+            println!("{}", result.message);
+        }
+        Commands::Migrate { input_file, dry_run } => {
+            info!("Executing Migrate command");
+            let result = commands::migrate::execute(input_file, *dry_run, &mut stdin_reader, &mut stdout)?;
+            println!("{}", result.message);
+        }
+        Commands::Codegen {
+            input_file,
+            lang,
+            output,
+            name,
+            check,
+        } => {
+            info!("Executing Codegen command");
+            let options = rpcfg::codegen::CodegenOptions { type_name: name.clone() };
+            let result = codegen::execute(
+                input_file,
+                *lang,
+                &options,
+                output.as_deref(),
+                *check,
+                &mut stdin_reader,
+                &mut stdout,
+            )?;
+            if matches!(result.status, Status::Error) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Schema { output } => {
+            info!("Executing Schema command");
+            let result = schema::execute(output.as_deref(), &mut stdin_reader, &mut stdout)?;
+            debug!("Schema command result: {:?}", result);
+        }
+        Commands::Template { action } => {
+            info!("Executing Template command");
+            let root = rp_macros::resolve_config_dir(cli.root.as_deref(), true)?;
+            let result = match action {
+                TemplateAction::List => template::list(&root, &mut stdin_reader, &mut stdout)?,
+                TemplateAction::Show { name } => template::show(&root, name, &mut stdin_reader, &mut stdout)?,
+                TemplateAction::Add {
+                    name,
+                    from,
+                    description,
+                    force,
+                } => {
+                    let result = template::add(
+                        &root,
+                        name,
+                        from,
+                        description.as_deref(),
+                        *force,
+                        &mut stdin_reader,
+                        &mut stdout,
+                    )?;
+                    println!("{}", result.message);
+                    result
+                }
+            };
+            debug!("Template command result: {:?}", result);
         }
     }
 
@@ -250,47 +717,66 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Collects the command line overrides, treating `--env` as an override of the `environment` item.
+fn overrides(cli: &Cli, env: &Option<String>) -> Vec<(String, String)> {
+    let mut overrides = cli.set.clone();
+    if let Some(env) = env {
+        overrides.push(("environment".to_string(), env.clone()));
+    }
+    overrides
+}
+
 /// Retrieves and parses the configuration file specified in the CLI arguments.
 ///
-/// This function checks if an input file path is provided in the CLI arguments,
-/// and if so, it attempts to parse the file into a `Config` struct.
+/// This function parses the input file into a `Config` struct and then resolves every
+/// value through the override layers (CLI flag > env > saved > shellscript > default).
 ///
 /// # Arguments
 ///
-/// * `cli` - A reference to the `Cli` struct containing parsed command-line arguments.
+/// * `input_file` - The path to the input JSON file.
+/// * `overrides` - The `--set KEY=VALUE` pairs from the command line.
+/// * `root` - The storage root from `--root`, if given.
 ///
 /// # Returns
 ///
 /// * `Result<Config>` - The parsed Config struct if successful, or an error if:
-///   - No input file is specified in the CLI arguments.
 ///   - The specified file cannot be read or parsed.
+///   - An override names an unknown key.
 ///
 /// # Errors
 ///
 /// This function will return an error if:
-/// * No input file is provided in the CLI arguments.
 /// * The `parse_config_file` function fails to read or parse the specified file.
+/// * The storage root cannot be determined.
+/// * The `resolve_values` function fails to apply the override layers.
 ///
 /// # Example
 ///
 /// ```
 /// let cli = Cli::parse();
-/// match get_config(&cli) {
+/// match get_config("repo_config.json", &cli.set, None) {
 ///     Ok(config) => println!("Configuration loaded successfully"),
 ///     Err(e) => eprintln!("Failed to load configuration: {}", e),
 /// }
 /// ```
-fn get_config(input_file: &str) -> Result<Config> {
+fn get_config(
+    input_file: &str,
+    overrides: &[(String, String)],
+    root: Option<&std::path::Path>,
+) -> Result<Config> {
     let mut config = parse_config_file(input_file)?;
     config.input_file = input_file.to_string();
+    config.root = root.map(PathBuf::from);
+    rp_macros::output_root(&config)?;
+    resolve::resolve_values(&mut config, overrides)?;
     Ok(config)
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Config, ConfigItem};
-    use crate::commands::collect::collect_user_input;
-    use crate::safe_test;
+    use rpcfg::{Config, ConfigItem};
+    use rpcfg::commands::collect::collect_user_input;
+    use rpcfg::safe_test;
     use std::fs;
     use std::io::Cursor;
     use uuid::Uuid;
@@ -317,15 +803,14 @@ mod tests {
     // });
 
     safe_test!(test_invalid_input, {
-        let test_id = Uuid::new_v4().to_string();
-        let mut config = create_test_config(&test_id);
+        let mut config = Config { is_test: true, ..Default::default() };
 
         let mut input = Cursor::new("invalid\n99\nq\n");
         let mut output = Cursor::new(Vec::new());
 
         let result = collect_user_input(&mut config, &mut input, &mut output)?;
 
-        assert!(matches!(result.status, crate::models::Status::Ok));
+        assert!(matches!(result.status, rpcfg::models::Status::Ok));
 
         let output_str = String::from_utf8(output.into_inner())?;
 
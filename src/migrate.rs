@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use tracing::{debug, warn};
+
+use crate::models::Config;
+
+/// The version of the input file format this build of rpcfg reads and writes.
+pub const CURRENT_FORMAT_VERSION: u32 = 1;
+
+/// A step that upgrades a file from one format version to the next, returning what it changed.
+type Migration = fn(&mut Map<String, Value>) -> Vec<String>;
+
+/// The migrations in order; the one at index `n` upgrades format version `n` to `n + 1`.
+const MIGRATIONS: &[Migration] = &[add_stored_item];
+
+/// Returns the format version of a parsed input file; files without `format_version` are version 0.
+///
+/// # Errors
+///
+/// This function will return an error if `format_version` is not a non-negative integer.
+pub fn format_version(file: &Value) -> Result<u32> {
+    match file.get("format_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| anyhow::anyhow!("format_version must be a non-negative integer, not {}", version)),
+    }
+}
+
+/// Upgrades a parsed input file to `CURRENT_FORMAT_VERSION`, in place.
+///
+/// Each migration between the file's version and the current one is applied in turn, and
+/// `format_version` is set to the current version.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - A description of each change made; empty if the file was current.
+///
+/// # Errors
+///
+/// This function will return an error if the file is not a JSON object, its `format_version`
+/// is not valid, or it was written by a newer rpcfg with a format this build does not know.
+pub fn upgrade(file: &mut Value) -> Result<Vec<String>> {
+    let version = format_version(file)?;
+    if version > CURRENT_FORMAT_VERSION {
+        anyhow::bail!(
+            "The file uses format version {}, but this rpcfg only reads up to version {}; upgrade rpcfg",
+            version,
+            CURRENT_FORMAT_VERSION
+        );
+    }
+    let Value::Object(map) = file else {
+        anyhow::bail!("Expected a JSON object");
+    };
+
+    let mut changes = Vec::new();
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        debug!("Migrating from format version {} to {}", from, from + 1);
+        changes.extend(migration(map));
+    }
+    if version < CURRENT_FORMAT_VERSION {
+        map.insert("format_version".to_string(), json!(CURRENT_FORMAT_VERSION));
+        changes.push(format!(
+            "Set format_version from {} to {}",
+            version, CURRENT_FORMAT_VERSION
+        ));
+    }
+    Ok(changes)
+}
+
+/// Reads an input file, upgrading it to the current format in memory.
+///
+/// The file itself is not changed; `rpcfg migrate` rewrites it.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file.
+/// * `name` - The name of the file for messages.
+///
+/// # Returns
+///
+/// * `Result<(Config, Vec<String>)>` - The upgraded configuration and the changes made (see `upgrade`).
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read, parsed or upgraded.
+pub fn read_upgraded(path: &Path, name: &str) -> Result<(Config, Vec<String>)> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to open file: {}", name))?;
+    let mut file: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse JSON from file: {}", name))?;
+    let changes = upgrade(&mut file).with_context(|| format!("Failed to upgrade file: {}", name))?;
+    if !changes.is_empty() {
+        warn!("{} uses an older format; run `rpcfg migrate -i {}` to upgrade it", name, name);
+    }
+    let config = serde_json::from_value(file)
+        .with_context(|| format!("Failed to parse JSON from file: {}", name))?;
+    Ok((config, changes))
+}
+
+/// Version 0 to 1: adds the `stored` rpcfg item, which older files could leave out.
+///
+/// Files without rpcfg items, such as those only used through `include`, are left alone, and
+/// so are files with `extends` or `include`: their `stored` comes from the files they build on,
+/// and an added item would override it.
+fn add_stored_item(file: &mut Map<String, Value>) -> Vec<String> {
+    let builds_on = |key: &str| file.get(key).is_some_and(|value| match value {
+        Value::Null => false,
+        Value::Array(bases) => !bases.is_empty(),
+        _ => true,
+    });
+    if builds_on("extends") || builds_on("include") {
+        return Vec::new();
+    }
+    let Some(Value::Array(rpcfg)) = file.get_mut("rpcfg") else {
+        return Vec::new();
+    };
+    let has_stored = rpcfg
+        .iter()
+        .any(|item| item.get("key").and_then(Value::as_str) == Some("stored"));
+    if rpcfg.is_empty() || has_stored {
+        return Vec::new();
+    }
+    rpcfg.push(json!({
+        "key": "stored",
+        "description": "Storage type for configuration",
+        "shellscript": "",
+        "default": "local",
+        "temp_environment_variable_name": "",
+        "required_as_env": false
+    }));
+    vec!["Added the rpcfg item 'stored' with the default 'local'".to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+
+    safe_test!(test_upgrade, {
+        let mut file = json!({
+            "rpcfg": [{"key": "project_name", "description": "Name", "default": "shop"}],
+            "app": []
+        });
+        let changes = upgrade(&mut file)?;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(file["rpcfg"][1]["key"], "stored");
+        assert_eq!(file["format_version"], CURRENT_FORMAT_VERSION);
+
+        // A current file is left as it is
+        assert!(upgrade(&mut file)?.is_empty());
+
+        // A file with only app items does not get rpcfg items
+        let mut include = json!({"rpcfg": [], "app": []});
+        upgrade(&mut include)?;
+        assert_eq!(include["rpcfg"], json!([]));
+
+        // A file building on others gets its `stored` from them
+        let mut child = json!({"extends": "base.json", "rpcfg": [{"key": "environment", "description": "Env", "default": "dev"}], "app": []});
+        upgrade(&mut child)?;
+        assert_eq!(child["rpcfg"].as_array().unwrap().len(), 1);
+
+        let mut newer = json!({"format_version": CURRENT_FORMAT_VERSION + 1, "rpcfg": [], "app": []});
+        assert!(upgrade(&mut newer).unwrap_err().to_string().contains("upgrade rpcfg"));
+        assert!(format_version(&json!({"format_version": "1"})).is_err());
+
+        Ok(())
+    });
+}
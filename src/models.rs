@@ -1,31 +1,338 @@
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use anyhow::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
-#[derive(Serialize, Clone, Deserialize, Debug)]
+/// A setting of the configuration.
+#[derive(Serialize, Clone, Deserialize, Debug, Default, JsonSchema)]
 pub struct ConfigItem {
+    /// The name of the setting, used as its key in the JSON output.
     pub key: String,
+    /// What the setting is for, shown when collecting its value.
     pub description: String,
+    /// A shell command whose trimmed output is the value, when no saved value, environment
+    /// variable or `--set` flag supplies one.
     #[serde(default)]
     pub shellscript: String,
+    /// The value used when nothing else supplies one.
     pub default: String,
+    /// An environment variable that overrides the value; `RPCFG_<KEY>` always does.
     #[serde(default)]
     pub temp_environment_variable_name: String,
+    /// Write the setting to the ENV output as `KEY=value`, with the key uppercased.
     #[serde(default)]
     pub required_as_env: bool,
-    //  this is here because we need a convinient place to collect the values from the user. we never store
-    //  the values in the input file, but rather in the output files.
+    /// Mask the value wherever rpcfg displays it; the outputs and `fetch` hold it as it is.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// The kind of value the setting holds; values that do not fit are rejected when collected.
+    #[serde(rename = "type", default, skip_serializing_if = "ItemType::is_string")]
+    pub item_type: ItemType,
+    /// Per-environment defaults, keyed by the value of the `environment` rpcfg item. `default`
+    /// is used for any environment that is not listed here.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, String>,
+    /// This is here because we need a convinient place to collect the values from the user. We
+    /// never store the values in the input file, but rather in the output files.
     #[serde(skip)]
     pub value: String,
+    /// Which layer supplied `value` (see `resolve::resolve_values`).
+    #[serde(skip)]
+    pub source: ValueSource,
+    /// The schema file this item was read from, when the input file uses `extends` or `include`.
+    #[serde(skip)]
+    pub defined_in: String,
+}
+
+impl ConfigItem {
+    /// Returns the default value for the given environment
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The environment name, as held by the `environment` rpcfg item
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The entry in `defaults` for that environment, or `default` if there is none
+    pub fn default_for(&self, environment: &str) -> &str {
+        self.defaults
+            .get(environment)
+            .map(|value| value.as_str())
+            .unwrap_or(&self.default)
+    }
+
+    /// Returns the value as it should be displayed, masked if the item is a secret
+    pub fn masked(&self, value: &str) -> String {
+        mask_value(value, self.secret)
+    }
+
+    /// Returns true if the key or description contains the pattern, ignoring case
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.to_lowercase();
+        self.key.to_lowercase().contains(&pattern)
+            || self.description.to_lowercase().contains(&pattern)
+    }
+
+    /// Checks whether a value is acceptable for this item
+    ///
+    /// The rpcfg items that choose the output location are checked: `stored` must name a
+    /// supported backend, and the project, config and environment names must be non-empty
+    /// and must not contain path separators. `history_limit` must be at least 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The candidate value
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok if the value is valid, or a message describing the problem
+    pub fn validate_value(&self, value: &str) -> Result<(), String> {
+        if !value.is_empty() {
+            self.item_type.check(value)?;
+        }
+        match self.key.as_str() {
+            "stored" if !STORAGE_TYPES.contains(&value) => Err(format!(
+                "'{}' is not a storage type; use one of: {}",
+                value,
+                STORAGE_TYPES.join(", ")
+            )),
+            "project_name" | "config_name" | "environment" => {
+                if value.trim().is_empty() {
+                    Err(format!("{} cannot be empty", self.key))
+                } else if value.contains(['/', '\\']) {
+                    Err(format!("{} cannot contain path separators", self.key))
+                } else {
+                    Ok(())
+                }
+            }
+            "history_limit" if !value.is_empty() && value.parse::<usize>().map_or(true, |limit| limit == 0) => {
+                Err("history_limit must be a whole number of at least 1".to_string())
+            }
+            "repo_dir" => {
+                let path = std::path::Path::new(value);
+                if value.trim().is_empty() {
+                    Err("repo_dir cannot be empty".to_string())
+                } else if path.is_absolute()
+                    || path.components().any(|c| c == std::path::Component::ParentDir)
+                {
+                    Err("repo_dir must be a path inside the directory of the input file".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a value like `validate_value`, masking it in the message if the item is secret
+    pub fn validate_masked(&self, value: &str) -> Result<(), String> {
+        self.validate_value(value).map_err(|e| {
+            if self.secret && !value.is_empty() {
+                e.replace(value, &self.masked(value))
+            } else {
+                e
+            }
+        })
+    }
+}
+
+/// Masks a secret value for display; empty values are left as they are so it is clear they are unset.
+pub fn mask_value(value: &str, secret: bool) -> String {
+    if secret && !value.is_empty() {
+        "********".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// The kind of value a ConfigItem holds, written as `"type"` in the input file.
+///
+/// Empty values are accepted for every type, so an item can be left unset.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Url,
+}
+
+impl ItemType {
+    pub fn is_string(&self) -> bool {
+        *self == ItemType::String
+    }
+
+    /// Guesses the type of an example value, falling back to `String`.
+    pub fn guess(value: &str) -> ItemType {
+        let value = value.trim();
+        if value.is_empty() {
+            ItemType::String
+        } else if matches!(value, "true" | "false") {
+            ItemType::Boolean
+        } else if value.parse::<i64>().is_ok() {
+            ItemType::Integer
+        } else if is_number(value) {
+            ItemType::Number
+        } else if value.contains("://") && !value.contains(char::is_whitespace) {
+            ItemType::Url
+        } else {
+            ItemType::String
+        }
+    }
+
+    /// Checks that a non-empty value fits the type.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let fits = match self {
+            ItemType::String => true,
+            ItemType::Integer => value.parse::<i64>().is_ok(),
+            ItemType::Number => is_number(value),
+            ItemType::Boolean => matches!(value, "true" | "false"),
+            ItemType::Url => ItemType::guess(value) == ItemType::Url,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!("'{}' is not a valid {}", value, self))
+        }
+    }
+}
+
+/// Returns true if the value is a finite number; `inf` and `NaN` are not settings anyone means.
+fn is_number(value: &str) -> bool {
+    value.parse::<f64>().is_ok_and(f64::is_finite)
 }
 
-#[derive(Serialize, Clone, Deserialize, Debug)]
+impl std::fmt::Display for ItemType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ItemType::String => "string",
+            ItemType::Integer => "integer",
+            ItemType::Number => "number",
+            ItemType::Boolean => "boolean",
+            ItemType::Url => "url",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The values accepted by the `stored` rpcfg item.
+pub const STORAGE_TYPES: &[&str] = &["local", "keyvault", "repo"];
+
+/// The layer that supplied a ConfigItem's current value.
+///
+/// Layers are applied lowest to highest precedence: `Default`, `Shellscript`, `Saved`,
+/// `Env`, `Cli`. A value typed in during `collect` is reported as `Collected`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    #[default]
+    Default,
+    Shellscript,
+    Saved,
+    Env,
+    Cli,
+    Collected,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueSource::Default => "default",
+            ValueSource::Shellscript => "shellscript",
+            ValueSource::Saved => "saved",
+            ValueSource::Env => "env",
+            ValueSource::Cli => "cli",
+            ValueSource::Collected => "collected",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An rpcfg input file: the settings of a configuration, whose values `rpcfg collect` saves.
+#[derive(Serialize, Clone, Deserialize, Debug, JsonSchema)]
 pub struct Config {
+    /// The JSON Schema editors validate the file against (see `rpcfg schema`); not used by rpcfg.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The version of the file format, upgraded by `rpcfg migrate`; files without it are version 0.
+    #[serde(default)]
+    pub format_version: u32,
+    /// A schema file whose items this file builds on, relative to this file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Further schema files merged after `extends`, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// The settings that control rpcfg itself, such as `stored`, `project_name`, `config_name`
+    /// and `environment`, which choose where the values are saved.
     pub rpcfg: Vec<ConfigItem>,
+    /// The settings of the application.
     pub app: Vec<ConfigItem>,
     #[serde(skip)]
     pub is_test: bool,
     #[serde(skip)]
     pub input_file: String,
+    /// The storage root from `--root`; None uses `rp_macros::resolve_root`.
+    #[serde(skip)]
+    pub root: Option<PathBuf>,
+    /// Set when settings are added, deleted, edited or moved, so the input file needs saving.
+    #[serde(skip)]
+    pub schema_changed: bool,
+    /// The values as of the last `mark_clean`, used to find unsaved changes.
+    #[serde(skip)]
+    pub clean_values: HashMap<String, String>,
+}
+
+/// A value that differs from the one recorded by `Config::mark_clean`.
+///
+/// `before` is `None` for a setting added since then and `after` is `None` for one deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingChange {
+    pub key: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl PendingChange {
+    /// Describes the change on one line, masking the values if `secret` is true.
+    ///
+    /// Changed values are shown as `~ key: 'before' -> 'after'`, added ones as
+    /// `+ key = 'value'` and removed ones as `- key`.
+    pub fn describe(&self, secret: bool) -> String {
+        let before = self.before.as_deref().map(|value| mask_value(value, secret));
+        let after = self.after.as_deref().map(|value| mask_value(value, secret));
+        match (before, after) {
+            (Some(before), Some(after)) => format!("~ {}: '{}' -> '{}'", self.key, before, after),
+            (None, Some(after)) => format!("+ {} = '{}'", self.key, after),
+            (Some(_), None) => format!("- {}", self.key),
+            (None, None) => format!("  {}", self.key),
+        }
+    }
+}
+
+/// Compares two flat key/value maps, such as two saved outputs.
+///
+/// # Returns
+///
+/// * `Vec<PendingChange>` - One entry per key that was added, removed or changed, sorted by key
+pub fn diff_values(
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) -> Vec<PendingChange> {
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(|key| PendingChange {
+            key: key.clone(),
+            before: before.get(key).cloned(),
+            after: after.get(key).cloned(),
+        })
+        .collect()
 }
 
 impl Config {
@@ -41,8 +348,6 @@ impl Config {
     /// # Returns
     ///
     /// * `Vec<&ConfigItem>` - A vector of references to all matching ConfigItems
-    ///
-
     pub fn get_settings(&self, key: &str) -> Vec<&ConfigItem> {
         self.rpcfg
             .iter()
@@ -73,6 +378,78 @@ impl Config {
         results
     }
 
+    /// Returns the part of the configuration that belongs in the input file itself
+    ///
+    /// Items inherited through `extends` or `include` are left out so that saving the input
+    /// file does not copy them into it. Items added or edited in this session are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `Config` - A copy of the configuration holding only the input file's own items
+    pub fn input_file_view(&self) -> Config {
+        let is_local = |item: &&ConfigItem| {
+            item.defined_in.is_empty() || item.defined_in == self.input_file
+        };
+        Config {
+            rpcfg: self.rpcfg.iter().filter(is_local).cloned().collect(),
+            app: self.app.iter().filter(is_local).cloned().collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Records the current values as saved and clears the schema change flag
+    pub fn mark_clean(&mut self) {
+        self.clean_values = self
+            .rpcfg
+            .iter()
+            .chain(self.app.iter())
+            .map(|item| (item.key.clone(), item.value.clone()))
+            .collect();
+        self.schema_changed = false;
+    }
+
+    /// Lists the values that changed since the last `mark_clean`
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PendingChange>` - Changed and added settings in table order, followed by deleted settings
+    pub fn pending_changes(&self) -> Vec<PendingChange> {
+        let mut changes: Vec<PendingChange> = self
+            .rpcfg
+            .iter()
+            .chain(self.app.iter())
+            .filter(|item| self.clean_values.get(&item.key) != Some(&item.value))
+            .map(|item| PendingChange {
+                key: item.key.clone(),
+                before: self.clean_values.get(&item.key).cloned(),
+                after: Some(item.value.clone()),
+            })
+            .collect();
+        let mut deleted: Vec<PendingChange> = self
+            .clean_values
+            .iter()
+            .filter(|(key, _)| self.get_settings(key).is_empty())
+            .map(|(key, value)| PendingChange {
+                key: key.clone(),
+                before: Some(value.clone()),
+                after: None,
+            })
+            .collect();
+        deleted.sort_by(|a, b| a.key.cmp(&b.key));
+        changes.extend(deleted);
+        changes
+    }
+
+    /// Returns true if the item with the given key is marked as a secret
+    pub fn is_secret(&self, key: &str) -> bool {
+        self.get_settings(key).iter().any(|item| item.secret)
+    }
+
+    /// Returns true if there are changes that have not been saved
+    pub fn is_dirty(&self) -> bool {
+        self.schema_changed || !self.pending_changes().is_empty()
+    }
+
     pub fn validate_rpcfg_config(&mut self) -> Result<(), Error> {
         for item in self.rpcfg.iter_mut() {
             if item.value.is_empty() {
@@ -87,7 +464,7 @@ impl Config {
 
         // Check if "stored" setting exists, if not, add it
         if let Some(stored_setting) = self.rpcfg.iter_mut().find(|item| item.key == "stored") {
-            if stored_setting.value != "local" && stored_setting.value != "keyvault" {
+            if !STORAGE_TYPES.contains(&stored_setting.value.as_str()) {
                 tracing::warn!(
                     "Invalid 'stored' value: '{}', setting to 'local'",
                     stored_setting.value
@@ -104,6 +481,7 @@ impl Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: "local".to_string(),
+                ..Default::default()
             });
         }
 
@@ -123,6 +501,7 @@ impl Default for Config {
                     temp_environment_variable_name: "".to_string(),
                     required_as_env: false,
                     value: "local".to_string(),
+                    ..Default::default()
                 },
                 ConfigItem {
                     key: "config_version".to_string(),
@@ -132,6 +511,7 @@ impl Default for Config {
                     temp_environment_variable_name: "".to_string(),
                     required_as_env: false,
                     value: "1.0".to_string(),
+                    ..Default::default()
                 },
                 ConfigItem {
                     key: "project_name".to_string(),
@@ -141,6 +521,7 @@ impl Default for Config {
                     temp_environment_variable_name: "".to_string(),
                     required_as_env: false,
                     value: "default_project_name".to_string(),
+                    ..Default::default()
                 },
                 ConfigItem {
                     key: "config_name".to_string(),
@@ -150,6 +531,7 @@ impl Default for Config {
                     temp_environment_variable_name: "".to_string(),
                     required_as_env: false,
                     value: "default_config_name".to_string(),
+                    ..Default::default()
                 },
                 ConfigItem {
                     key: "environment".to_string(),
@@ -159,11 +541,19 @@ impl Default for Config {
                     temp_environment_variable_name: "".to_string(),
                     required_as_env: false,
                     value: "default_env".to_string(),
+                    ..Default::default()
                 },
             ],
+            schema: None,
+            format_version: crate::migrate::CURRENT_FORMAT_VERSION,
+            extends: None,
+            include: Vec::new(),
             app: Vec::new(),
             is_test: false,
             input_file: String::new(),
+            root: None,
+            schema_changed: false,
+            clean_values: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, warn};
+
+use crate::json_output_uri;
+use crate::models::{Config, ConfigItem, ValueSource};
+
+/// Prefix for the generic per-item override variable (`RPCFG_<KEY>`).
+pub const ENV_PREFIX: &str = "RPCFG_";
+
+/// Returns the environment variable names that can override an item, in the order they are checked.
+///
+/// The item's `temp_environment_variable_name` wins over the generic `RPCFG_<KEY>` name.
+pub fn env_var_names(item: &ConfigItem) -> Vec<String> {
+    let mut names = Vec::new();
+    if !item.temp_environment_variable_name.is_empty() {
+        names.push(item.temp_environment_variable_name.clone());
+    }
+    names.push(format!("{}{}", ENV_PREFIX, item.key.to_uppercase()));
+    names
+}
+
+/// Resolves the value of every item in the configuration by applying the value layers.
+///
+/// Layers are applied from lowest to highest precedence:
+///
+/// 1. `default` from the input file, or the entry in `defaults` for the current environment
+/// 2. the output of the item's `shellscript`, if it has one
+/// 3. the value saved in the JSON output file by a previous `collect`
+/// 4. an environment variable (see `env_var_names`)
+/// 5. a `--set KEY=VALUE` flag on the command line
+///
+/// A shellscript only runs when none of the saved, environment or command line layers supplies
+/// the value, so commands that only read values never run it needlessly.
+///
+/// The `environment` item is resolved first because it selects the per-environment defaults.
+/// The rpcfg items select the output file, so they are resolved before the saved values are
+/// loaded and are never taken from the saved file themselves.
+///
+/// # Arguments
+///
+/// * `config` - A mutable reference to the Config object to be resolved.
+/// * `cli_overrides` - The `KEY=VALUE` pairs passed on the command line.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * A command line override names a key that is not in the configuration.
+/// * The saved JSON output file exists but cannot be read or parsed.
+pub fn resolve_values(config: &mut Config, cli_overrides: &[(String, String)]) -> Result<()> {
+    for (key, _) in cli_overrides {
+        if config.get_settings(key).is_empty() {
+            anyhow::bail!("Unknown setting '{}' passed to --set", key);
+        }
+    }
+
+    let mut environment = String::new();
+    if let Some(item) = config.rpcfg.iter_mut().find(|item| item.key == "environment") {
+        item.value = item.default.clone();
+        item.source = ValueSource::Default;
+        resolve_item(item, None, cli_overrides);
+        environment = item.value.clone();
+    }
+    debug!("Resolving values for environment '{}'", environment);
+
+    for item in config.rpcfg.iter_mut().filter(|item| item.key != "environment") {
+        item.value = item.default_for(&environment).to_string();
+        item.source = ValueSource::Default;
+        resolve_item(item, None, cli_overrides);
+    }
+    config.validate_rpcfg_config()?;
+
+    let saved = load_saved_values(config)?;
+    for item in config.app.iter_mut() {
+        item.value = item.default_for(&environment).to_string();
+        item.source = ValueSource::Default;
+        resolve_item(item, saved.get(&item.key), cli_overrides);
+    }
+
+    Ok(())
+}
+
+/// Applies the layers above the default to a single item.
+///
+/// The shellscript runs last, and only if no saved value, environment variable or command line
+/// override was found.
+fn resolve_item(item: &mut ConfigItem, saved: Option<&String>, cli_overrides: &[(String, String)]) {
+    if let Some(value) = saved {
+        item.value = value.clone();
+        item.source = ValueSource::Saved;
+    }
+    apply_overrides(item, cli_overrides);
+    if item.source == ValueSource::Default {
+        apply_shellscript(item);
+    }
+}
+
+/// Loads the flat key/value map from the JSON output file, or an empty map if it does not exist yet.
+pub fn load_saved_values(config: &Config) -> Result<HashMap<String, String>> {
+    let json_path = match json_output_uri!(config) {
+        Some(path) => path,
+        None => return Ok(HashMap::new()),
+    };
+    if !Path::new(&json_path).exists() {
+        debug!("No saved values at {}", json_path);
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&json_path)
+        .with_context(|| format!("Failed to read JSON file: {}", json_path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse JSON from file: {}", json_path))
+}
+
+/// Runs the item's shellscript, if any, and uses its trimmed stdout as the value.
+fn apply_shellscript(item: &mut ConfigItem) {
+    if item.shellscript.trim().is_empty() {
+        return;
+    }
+    match Command::new("sh").arg("-c").arg(&item.shellscript).output() {
+        Ok(out) if out.status.success() => {
+            let value = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            if !value.is_empty() {
+                item.value = value;
+                item.source = ValueSource::Shellscript;
+            }
+        }
+        Ok(out) => warn!(
+            "shellscript for '{}'{} exited with {}: {}",
+            item.key,
+            defined_in(item),
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        ),
+        Err(e) => warn!(
+            "Failed to run shellscript for '{}'{}: {}",
+            item.key,
+            defined_in(item),
+            e
+        ),
+    }
+}
+
+/// Describes where an item was defined, for messages.
+fn defined_in(item: &ConfigItem) -> String {
+    if item.defined_in.is_empty() {
+        String::new()
+    } else {
+        format!(" (defined in {})", item.defined_in)
+    }
+}
+
+/// Applies the environment and command line layers to a single item.
+fn apply_overrides(item: &mut ConfigItem, cli_overrides: &[(String, String)]) {
+    if let Some(value) = env_var_names(item)
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+    {
+        debug!("Overriding '{}' from the environment", item.key);
+        item.value = value;
+        item.source = ValueSource::Env;
+    }
+    if let Some((_, value)) = cli_overrides.iter().rev().find(|(key, _)| *key == item.key) {
+        debug!("Overriding '{}' from the command line", item.key);
+        item.value = value.clone();
+        item.source = ValueSource::Cli;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collect;
+    use crate::{create_test_input_file, parse_config_file, safe_test};
+    use std::io::Cursor;
+
+    safe_test!(test_override_precedence, {
+        let (config, _temp_dir) = create_test_input_file!("resolve_precedence");
+        let item1 = config.app[0].clone();
+        let item2 = config.app[1].clone();
+
+        // Save a value for both app items
+        {
+            let mut config = parse_config_file(&config.input_file)?;
+            config.is_test = true;
+            let mut input = Cursor::new("6\nsaved1\n7\nsaved2\ns\n");
+            collect::collect_user_input(&mut config, &mut input, &mut Cursor::new(Vec::new()))?;
+            // collect exports required items to the process environment; clear it for the checks below
+            std::env::remove_var(&item1.temp_environment_variable_name);
+        }
+
+        let mut config = parse_config_file(&config.input_file)?;
+        config.is_test = true;
+
+        // Saved beats default
+        resolve_values(&mut config, &[])?;
+        assert_eq!(config.get_settings(&item1.key)[0].value, "saved1");
+        assert_eq!(config.get_settings(&item1.key)[0].source, ValueSource::Saved);
+
+        // Env (both the declared name and RPCFG_<KEY>) beats saved
+        std::env::set_var(&item1.temp_environment_variable_name, "from_env");
+        let generic = format!("{}{}", ENV_PREFIX, item2.key.to_uppercase());
+        std::env::set_var(&generic, "from_generic_env");
+        resolve_values(&mut config, &[])?;
+        assert_eq!(config.get_settings(&item1.key)[0].value, "from_env");
+        assert_eq!(config.get_settings(&item1.key)[0].source, ValueSource::Env);
+        assert_eq!(config.get_settings(&item2.key)[0].value, "from_generic_env");
+
+        // CLI beats env
+        resolve_values(&mut config, &[(item1.key.clone(), "from_cli".to_string())])?;
+        assert_eq!(config.get_settings(&item1.key)[0].value, "from_cli");
+        assert_eq!(config.get_settings(&item1.key)[0].source, ValueSource::Cli);
+
+        std::env::remove_var(&item1.temp_environment_variable_name);
+        std::env::remove_var(&generic);
+
+        // Unknown keys are rejected
+        assert!(resolve_values(&mut config, &[("nope".to_string(), "x".to_string())]).is_err());
+
+        Ok(())
+    });
+
+    safe_test!(test_per_environment_defaults, {
+        let (mut config, _temp_dir) = create_test_input_file!("resolve_environments");
+        config.app[1]
+            .defaults
+            .insert("production".to_string(), "prod_value".to_string());
+
+        // The environment item's own default has no entry, so the plain default applies
+        resolve_values(&mut config, &[])?;
+        assert_eq!(config.app[1].value, "default2");
+
+        // Selecting the environment picks up the per-environment default
+        resolve_values(&mut config, &[("environment".to_string(), "production".to_string())])?;
+        assert_eq!(config.get_settings("environment")[0].value, "production");
+        assert_eq!(config.app[1].value, "prod_value");
+        assert_eq!(config.app[1].source, ValueSource::Default);
+
+        Ok(())
+    });
+
+    safe_test!(test_shellscript_layer, {
+        let (mut config, temp_dir) = create_test_input_file!("resolve_shellscript");
+        config.app[1].shellscript = "echo from_script".to_string();
+
+        resolve_values(&mut config, &[])?;
+        assert_eq!(config.app[1].value, "from_script");
+        assert_eq!(config.app[1].source, ValueSource::Shellscript);
+
+        // A value from a higher layer means the shellscript is not run at all
+        let marker = temp_dir.path().join("script_ran");
+        config.app[1].shellscript = format!("touch {}", marker.display());
+        let key = config.app[1].key.clone();
+        resolve_values(&mut config, &[(key, "from_cli".to_string())])?;
+        assert_eq!(config.app[1].value, "from_cli");
+        assert!(!marker.exists());
+        resolve_values(&mut config, &[])?;
+        assert!(marker.exists());
+
+        Ok(())
+    });
+}
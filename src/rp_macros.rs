@@ -1,5 +1,5 @@
 use std::sync::atomic::{AtomicUsize, AtomicBool};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Global flag for verbose output
 pub static VERBOSE: AtomicBool = AtomicBool::new(false);
@@ -15,18 +15,11 @@ macro_rules! get_home_dir {
     }};
 }
 
+/// Resolves the storage root of a configuration (see `storage_root`).
 #[macro_export]
 macro_rules! get_rp_dir {
     ($config:expr) => {{
-        if $config.is_test {
-            std::env::temp_dir().join(".rpcfg")
-        } else {
-            std::env::var("HOME")
-                .or_else(|_| std::env::var("USERPROFILE"))
-                .map(std::path::PathBuf::from)
-                .unwrap_or_else(|_| std::env::temp_dir())
-                .join(".rpcfg")
-        }
+        $crate::rp_macros::storage_root($config)
     }};
 }
 
@@ -37,6 +30,155 @@ macro_rules! get_base_name {
     }};
 }
 
+/// Environment variable that sets the storage root.
+pub const RPCFG_HOME_VAR: &str = "RPCFG_HOME";
+
+/// Returns the storage root used before XDG support, `~/.rpcfg`.
+pub fn legacy_root() -> Option<PathBuf> {
+    get_home_dir!().map(|home| home.join(".rpcfg"))
+}
+
+/// Resolves the directory that holds the outputs and snapshots of every project.
+///
+/// The first of these wins:
+/// 1. `root`, from the `--root` option.
+/// 2. The `RPCFG_HOME` environment variable.
+/// 3. `~/.rpcfg`, if `use_legacy` is true and the directory exists (see `rpcfg relocate`).
+/// 4. `$XDG_DATA_HOME/rpcfg`.
+/// 5. `~/.local/share/rpcfg`.
+///
+/// # Errors
+///
+/// This function will return an error if no override is set and the home directory is unknown.
+/// There is deliberately no fallback to the temp directory, which may be cleaned at any time.
+pub fn resolve_root(root: Option<&Path>, use_legacy: bool) -> anyhow::Result<PathBuf> {
+    resolve_dir(root, use_legacy, "XDG_DATA_HOME", &[".local", "share"], "storage root")
+}
+
+/// Resolves the directory that holds the user's own settings for rpcfg, such as templates.
+///
+/// The first of these wins:
+/// 1. `root`, from the `--root` option.
+/// 2. The `RPCFG_HOME` environment variable.
+/// 3. `~/.rpcfg`, if `use_legacy` is true and the directory exists (see `rpcfg relocate`).
+/// 4. `$XDG_CONFIG_HOME/rpcfg`.
+/// 5. `~/.config/rpcfg`.
+///
+/// An explicit root, or the legacy one, holds everything, so it is the storage root as well.
+///
+/// # Errors
+///
+/// This function will return an error if no override is set and the home directory is unknown.
+pub fn resolve_config_dir(root: Option<&Path>, use_legacy: bool) -> anyhow::Result<PathBuf> {
+    resolve_dir(root, use_legacy, "XDG_CONFIG_HOME", &[".config"], "config directory")
+}
+
+/// Applies the overrides shared by `resolve_root` and `resolve_config_dir`, then the XDG
+/// variable `xdg_var`, then `home_dir` under the home directory, each followed by `rpcfg`.
+fn resolve_dir(
+    root: Option<&Path>,
+    use_legacy: bool,
+    xdg_var: &str,
+    home_dir: &[&str],
+    what: &str,
+) -> anyhow::Result<PathBuf> {
+    let non_empty = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    if let Some(root) = root {
+        return Ok(root.to_path_buf());
+    }
+    if let Some(root) = non_empty(RPCFG_HOME_VAR) {
+        return Ok(PathBuf::from(root));
+    }
+    if use_legacy {
+        if let Some(legacy) = legacy_root().filter(|legacy| legacy.is_dir()) {
+            return Ok(legacy);
+        }
+    }
+    if let Some(xdg_home) = non_empty(xdg_var) {
+        return Ok(PathBuf::from(xdg_home).join("rpcfg"));
+    }
+    get_home_dir!()
+        .map(|home| home_dir.iter().fold(home, |dir, part| dir.join(part)).join("rpcfg"))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Cannot find the rpcfg {}: HOME is not set. Set {} or pass --root",
+                what,
+                RPCFG_HOME_VAR
+            )
+        })
+}
+
+/// Returns the storage root of a configuration: its `root` if set, a directory under the
+/// temp dir for tests, and otherwise the location chosen by `resolve_root`.
+pub fn storage_root(config: &crate::Config) -> anyhow::Result<PathBuf> {
+    if let Some(root) = &config.root {
+        Ok(root.clone())
+    } else if config.is_test {
+        Ok(std::env::temp_dir().join(".rpcfg"))
+    } else {
+        resolve_root(None, true)
+    }
+}
+
+/// Returns true if the configuration is stored in the legacy `~/.rpcfg` root.
+pub fn uses_legacy_root(config: &crate::Config) -> bool {
+    config.root.is_none()
+        && !config.is_test
+        && std::env::var(RPCFG_HOME_VAR).map_or(true, |value| value.is_empty())
+        && storage_root(config).ok() == legacy_root()
+}
+
+/// The directory, relative to the input file, that holds the outputs when `stored` is `repo`.
+pub const DEFAULT_REPO_DIR: &str = ".rpcfg";
+
+/// Returns the output directory for `stored: "repo"`, from the optional `repo_dir` rpcfg item.
+///
+/// Relative to the directory of the input file.
+///
+/// # Errors
+///
+/// This function will return an error if `repo_dir` is absolute or leaves the directory of
+/// the input file, so outputs are never written outside the repository.
+pub fn repo_dir(config: &crate::Config) -> anyhow::Result<String> {
+    match config
+        .get_settings("repo_dir")
+        .into_iter()
+        .find(|item| !item.value.trim().is_empty())
+    {
+        Some(item) => {
+            item.validate_value(&item.value)
+                .map_err(|e| anyhow::anyhow!("Invalid repo_dir '{}': {}", item.value, e))?;
+            Ok(item.value.clone())
+        }
+        None => Ok(DEFAULT_REPO_DIR.to_string()),
+    }
+}
+
+/// Returns the directory of the input file, `.` for a bare file name.
+pub fn input_dir(config: &crate::Config) -> Option<PathBuf> {
+    if config.input_file.is_empty() {
+        return None;
+    }
+    match Path::new(&config.input_file).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Some(dir.to_path_buf()),
+        _ => Some(PathBuf::from(".")),
+    }
+}
+
+/// Returns the directory under which a configuration's outputs are written: the repo
+/// output directory for `stored: "repo"`, the storage root otherwise.
+pub fn output_root(config: &crate::Config) -> anyhow::Result<PathBuf> {
+    if config.get_settings("stored").iter().any(|item| item.value == "repo") {
+        let repo_dir = repo_dir(config)?;
+        input_dir(config)
+            .map(|dir| dir.join(repo_dir))
+            .ok_or_else(|| anyhow::anyhow!("Storage type repo needs the path of the input file"))
+    } else {
+        storage_root(config)
+    }
+}
+
 pub fn base_output_dir(config: &crate::Config) -> Option<PathBuf> {
     let stored = config.get_settings("stored").first().map(|item| item.value.as_str()).unwrap_or("local");
     let project_name = config.get_settings("project_name").first().map(|item| item.value.as_str()).unwrap_or("default_project");
@@ -50,9 +192,32 @@ pub fn base_output_dir(config: &crate::Config) -> Option<PathBuf> {
     tracing::debug!("Environment: {}", environment);    
 
     if stored == "local" {
-        Some(crate::get_rp_dir!(config)
+        let root = match crate::get_rp_dir!(config) {
+            Ok(root) => root,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return None;
+            }
+        };
+        Some(root
             .join(project_name)
             .join(format!("{}-{}", config_name, environment)))
+    } else if stored == "repo" {
+        // Outputs live next to the input file, so each checkout has its own
+        let Some(input_dir) = input_dir(config) else {
+            tracing::error!("Storage type repo needs the path of the input file");
+            return None;
+        };
+        let repo_dir = match repo_dir(config) {
+            Ok(repo_dir) => repo_dir,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return None;
+            }
+        };
+        Some(input_dir
+            .join(repo_dir)
+            .join(format!("{}-{}", config_name, environment)))
     } else {
         tracing::error!("Storage type {} is not supported", stored);
         None
@@ -113,10 +278,11 @@ macro_rules! get_root_dir {
     }};
 }
 
+#[cfg(test)]
 #[macro_export]
 macro_rules! create_test_config {
     ($test_id:expr) => {
-        crate::test_utils::create_test_config($test_id)
+        $crate::test_utils::create_test_config($test_id)
     };
 }
 
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+use crate::files::{create_private_dir, write_all_or_none};
+use crate::models::Config;
+use crate::rp_macros::base_output_dir;
+
+/// The number of snapshots kept per configuration when `history_limit` is not set.
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// A saved copy of the JSON and ENV output files.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub id: String,
+    pub json_path: PathBuf,
+    pub env_path: PathBuf,
+}
+
+impl Snapshot {
+    /// Reads the flat key/value map saved in the snapshot.
+    pub fn values(&self) -> Result<HashMap<String, String>> {
+        let content = fs::read_to_string(&self.json_path)
+            .with_context(|| format!("Failed to read snapshot: {}", self.json_path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse snapshot: {}", self.json_path.display()))
+    }
+}
+
+/// Returns the directory holding the snapshots of a configuration.
+///
+/// Snapshots live next to the outputs, under `<project>/.history/<config>-<environment>/`.
+pub fn history_dir(config: &Config) -> Option<PathBuf> {
+    let base_dir = base_output_dir(config)?;
+    let name = base_dir.file_name()?.to_owned();
+    Some(base_dir.parent()?.join(".history").join(name))
+}
+
+/// Returns the number of snapshots to keep, from the optional `history_limit` rpcfg item.
+///
+/// At least one is always kept, so the snapshot of the last save can be rolled back to.
+pub fn history_limit(config: &Config) -> usize {
+    config
+        .get_settings("history_limit")
+        .first()
+        .and_then(|item| item.value.parse().ok())
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .max(1)
+}
+
+/// Records a snapshot of the contents just written to the output files.
+///
+/// Snapshots beyond the retention limit are removed, oldest first.
+///
+/// # Arguments
+///
+/// * `config` - The configuration that was saved.
+/// * `json_content` - The contents of the JSON output file.
+/// * `env_content` - The contents of the ENV output file.
+///
+/// # Returns
+///
+/// * `Result<Snapshot>` - The snapshot that was written.
+///
+/// # Errors
+///
+/// This function will return an error if the history directory cannot be created or written.
+pub fn record(config: &Config, json_content: &str, env_content: &str) -> Result<Snapshot> {
+    let dir = history_dir(config)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get history directory"))?;
+    create_private_dir(&dir)
+        .with_context(|| format!("Failed to create history directory: {}", dir.display()))?;
+
+    let stamp = format_timestamp(SystemTime::now());
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while dir.join(format!("{}.json", id)).exists() {
+        id = format!("{}-{}", stamp, counter);
+        counter += 1;
+    }
+
+    let snapshot = Snapshot {
+        json_path: dir.join(format!("{}.json", id)),
+        env_path: dir.join(format!("{}.env", id)),
+        id,
+    };
+    write_all_or_none(&[
+        (snapshot.json_path.clone(), json_content.to_string()),
+        (snapshot.env_path.clone(), env_content.to_string()),
+    ])?;
+    debug!("Recorded snapshot {}", snapshot.id);
+
+    prune(&dir, history_limit(config))?;
+    Ok(snapshot)
+}
+
+/// Lists the snapshots of a configuration, oldest first.
+pub fn list(config: &Config) -> Result<Vec<Snapshot>> {
+    let dir = match history_dir(config) {
+        Some(dir) if dir.exists() => dir,
+        _ => return Ok(Vec::new()),
+    };
+    list_in(&dir)
+}
+
+/// Finds a snapshot by id.
+pub fn find(config: &Config, id: &str) -> Result<Snapshot> {
+    list(config)?
+        .into_iter()
+        .find(|snapshot| snapshot.id == id)
+        .ok_or_else(|| anyhow::anyhow!("No snapshot with id '{}'", id))
+}
+
+fn list_in(dir: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots: Vec<Snapshot> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|json_path| {
+            let id = json_path.file_stem()?.to_string_lossy().into_owned();
+            Some(Snapshot {
+                env_path: json_path.with_extension("env"),
+                json_path,
+                id,
+            })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| natural_id_order(&a.id, &b.id));
+    Ok(snapshots)
+}
+
+/// Orders ids by timestamp, then by the numeric suffix added for same-millisecond saves.
+fn natural_id_order(a: &str, b: &str) -> std::cmp::Ordering {
+    let split = |id: &str| -> (String, u32) {
+        match id.split_once('-') {
+            Some((stamp, n)) => (stamp.to_string(), n.parse().unwrap_or(0)),
+            None => (id.to_string(), 0),
+        }
+    };
+    split(a).cmp(&split(b))
+}
+
+fn prune(dir: &Path, limit: usize) -> Result<()> {
+    let snapshots = list_in(dir)?;
+    if snapshots.len() <= limit {
+        return Ok(());
+    }
+    for snapshot in &snapshots[..snapshots.len() - limit] {
+        debug!("Removing snapshot {} beyond the retention limit", snapshot.id);
+        fs::remove_file(&snapshot.json_path)?;
+        if snapshot.env_path.exists() {
+            fs::remove_file(&snapshot.env_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats a time as a sortable UTC timestamp, e.g. `20261018T125443123Z`.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Formats a time for display, e.g. `2026-10-18 12:54:43 UTC`.
+pub fn format_display_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) date in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use std::time::Duration;
+
+    safe_test!(test_format_timestamp, {
+        let time = UNIX_EPOCH + Duration::from_millis(1_792_328_083_123);
+        assert_eq!(format_timestamp(time), "20261018T125443123Z");
+        assert_eq!(format_display_time(time), "2026-10-18 12:54:43 UTC");
+        assert_eq!(format_timestamp(UNIX_EPOCH), "19700101T000000000Z");
+        Ok(())
+    });
+}
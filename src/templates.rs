@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::files::{create_private_dir, write_atomic};
+use crate::models::ConfigItem;
+
+/// The templates shipped with rpcfg, as name and JSON.
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("azure", include_str!("../templates/azure.json")),
+    ("github-app", include_str!("../templates/github-app.json")),
+    ("postgres", include_str!("../templates/postgres.json")),
+];
+
+/// The directory under the config directory (see `rp_macros::resolve_config_dir`) that holds
+/// the user templates.
+pub const TEMPLATES_DIR: &str = ".templates";
+
+/// A set of app items to start a schema from (see `rpcfg init --template`).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Template {
+    #[serde(skip)]
+    pub name: String,
+    //  true for the templates shipped with rpcfg
+    #[serde(skip)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub description: String,
+    pub app: Vec<ConfigItem>,
+}
+
+impl Template {
+    fn parse(name: &str, content: &str, builtin: bool) -> Result<Template> {
+        let mut template: Template = serde_json::from_str(content)
+            .with_context(|| format!("Failed to parse template: {}", name))?;
+        template.name = name.to_string();
+        template.builtin = builtin;
+        Ok(template)
+    }
+}
+
+/// Returns the directory holding the user templates under a config directory.
+pub fn user_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join(TEMPLATES_DIR)
+}
+
+/// Returns true if the name can be used for a template: letters, digits, `-` and `_`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Lists the built-in and user templates, sorted by name.
+///
+/// A user template with the name of a built-in one replaces it.
+///
+/// # Errors
+///
+/// This function will return an error if the user template directory cannot be read, or a
+/// template in it cannot be parsed.
+pub fn list(config_dir: &Path) -> Result<Vec<Template>> {
+    let mut templates = BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, content)| Template::parse(name, content, true))
+        .collect::<Result<Vec<_>>>()?;
+
+    let dir = user_dir(config_dir);
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+            let path = entry?.path();
+            let Some(name) = path
+                .file_stem()
+                .filter(|_| path.extension().is_some_and(|ext| ext == "json"))
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template: {}", path.display()))?;
+            let template = Template::parse(&name, &content, false)?;
+            debug!("Found user template {} at {}", name, path.display());
+            templates.retain(|existing| existing.name != name);
+            templates.push(template);
+        }
+    }
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Finds a template by name.
+///
+/// # Errors
+///
+/// This function will return an error if there is no such template, listing the ones there are.
+pub fn find(config_dir: &Path, name: &str) -> Result<Template> {
+    let templates = list(config_dir)?;
+    let names: Vec<&str> = templates.iter().map(|template| template.name.as_str()).collect();
+    let names = names.join(", ");
+    templates
+        .iter()
+        .find(|template| template.name == name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No template named '{}'; the templates are: {}", name, names))
+}
+
+/// Saves a user template, returning its path.
+///
+/// # Errors
+///
+/// This function will return an error if the name is not valid, a user template with the name
+/// exists and `force` is false, or the file cannot be written.
+pub fn add(config_dir: &Path, template: &Template, force: bool) -> Result<PathBuf> {
+    if !is_valid_name(&template.name) {
+        anyhow::bail!(
+            "'{}' is not a valid template name; use letters, digits, '-' and '_'",
+            template.name
+        );
+    }
+    let dir = user_dir(config_dir);
+    let path = dir.join(format!("{}.json", template.name));
+    if path.exists() && !force {
+        anyhow::bail!("Template {} already exists at {}; use --force to replace it", template.name, path.display());
+    }
+    create_private_dir(&dir)?;
+    write_atomic(&path, &serde_json::to_string_pretty(template)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+    use tempfile::TempDir;
+
+    safe_test!(test_templates, {
+        let root = TempDir::new()?;
+        let names: Vec<String> = list(root.path())?.into_iter().map(|template| template.name).collect();
+        assert_eq!(names, vec!["azure", "github-app", "postgres"]);
+        let postgres = find(root.path(), "postgres")?;
+        assert!(postgres.builtin);
+        assert!(postgres.app.iter().any(|item| item.key == "database_password" && item.secret));
+
+        // A user template replaces the built-in one of the same name
+        let template = Template {
+            name: "postgres".to_string(),
+            description: "Our Postgres settings".to_string(),
+            app: postgres.app[..1].to_vec(),
+            ..Default::default()
+        };
+        add(root.path(), &template, false)?;
+        assert!(add(root.path(), &template, false).is_err());
+        let replaced = find(root.path(), "postgres")?;
+        assert!(!replaced.builtin);
+        assert_eq!(replaced.app.len(), 1);
+        assert_eq!(list(root.path())?.len(), 3);
+
+        assert!(find(root.path(), "missing").unwrap_err().to_string().contains("azure"));
+        let bad = Template {
+            name: "../escape".to_string(),
+            ..Default::default()
+        };
+        assert!(add(root.path(), &bad, false).is_err());
+
+        Ok(())
+    });
+}
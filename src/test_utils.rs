@@ -1,12 +1,16 @@
 use crate::models::{Config, ConfigItem};
-use std::fs;
-
-use serde_json;
 
 pub fn create_test_config(test_id: &str) -> Config {
     Config {
         is_test: true,
         input_file: format!("test_input_{}.json", test_id),
+        root: None,
+        schema: None,
+        format_version: crate::migrate::CURRENT_FORMAT_VERSION,
+        extends: None,
+        include: Vec::new(),
+        schema_changed: false,
+        clean_values: Default::default(),
         rpcfg: vec![
             ConfigItem {
                 key: "stored".to_string(),
@@ -16,6 +20,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: "local".to_string(), // Set a default value
+                ..Default::default()
             },
             ConfigItem {
                 key: "config_version".to_string(),
@@ -25,6 +30,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: "1.0".to_string(), // Set a default value
+                ..Default::default()
             },
             ConfigItem {
                 key: "project_name".to_string(),
@@ -34,6 +40,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: format!("project_{}", test_id), // Set a default value
+                ..Default::default()
             },
             ConfigItem {
                 key: "config_name".to_string(),
@@ -43,6 +50,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: format!("config_{}", test_id), // Set a default value
+                ..Default::default()
             },
             ConfigItem {
                 key: "environment".to_string(),
@@ -52,6 +60,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: format!("env_{}", test_id), // Set a default value
+                ..Default::default()
             },
         ],
         app: vec![
@@ -63,6 +72,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: format!("TEST_ITEM_1_{}", test_id),
                 required_as_env: true,
                 value: "initial_value1".to_string(),
+                ..Default::default()
             },
             ConfigItem {
                 key: format!("item2_{}", test_id),
@@ -72,6 +82,7 @@ pub fn create_test_config(test_id: &str) -> Config {
                 temp_environment_variable_name: "".to_string(),
                 required_as_env: false,
                 value: "".to_string(),
+                ..Default::default()
             },
         ],
     }
@@ -85,7 +96,7 @@ macro_rules! create_test_input_file {
         let input_path = temp_dir.path().join(format!("input-{}.json", test_id));
         
         // Create test config
-        let mut config = crate::create_test_config(&test_id);
+        let mut config = $crate::create_test_config(&test_id);
         config.input_file = input_path.to_str().unwrap().to_string();
         
         // Save the config to the file
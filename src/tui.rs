@@ -0,0 +1,497 @@
+use anyhow::Result;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{
+    self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use std::io::Write;
+use tracing::debug;
+
+use crate::models::{Config, ValueSource};
+
+/// Help text shown at the bottom of the screen in normal mode.
+const HELP: &str = "Up/Down move  Enter edit  / search  Esc clear search  s save  q quit";
+
+/// How a full-screen collect session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Save,
+    Quit,
+}
+
+/// What the keyboard is currently driving.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Editing(String),
+    Searching(String),
+    ConfirmQuit,
+}
+
+/// A row of the settings table: a section header or an item (by combined index).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Header(&'static str),
+    Item(usize),
+}
+
+/// How a line of the screen is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Header,
+    Row,
+    Selected,
+    Error,
+    Footer,
+}
+
+/// The state of a full-screen collect session, independent of the terminal.
+///
+/// Key presses are fed to `handle_key` and the screen is produced by `lines`, so the
+/// behaviour can be exercised without a TTY.
+pub struct TuiState {
+    selected: usize,
+    filter: String,
+    mode: Mode,
+    error: Option<String>,
+    status: Option<String>,
+    outcome: Option<Outcome>,
+}
+
+impl Default for TuiState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuiState {
+    pub fn new() -> Self {
+        TuiState {
+            selected: 0,
+            filter: String::new(),
+            mode: Mode::Normal,
+            error: None,
+            status: None,
+            outcome: None,
+        }
+    }
+
+    /// Returns how the session ended, once it has.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the combined indexes of the items that match the current filter.
+    fn visible_items(&self, config: &Config) -> Vec<usize> {
+        config
+            .rpcfg
+            .iter()
+            .chain(config.app.iter())
+            .enumerate()
+            .filter(|(_, item)| item.matches(&self.filter))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the table rows, with a header ahead of each non-empty section.
+    fn rows(&self, config: &Config) -> Vec<Row> {
+        let visible = self.visible_items(config);
+        let mut rows = Vec::new();
+        let (rpcfg, app): (Vec<usize>, Vec<usize>) =
+            visible.into_iter().partition(|index| *index < config.rpcfg.len());
+        if !rpcfg.is_empty() {
+            rows.push(Row::Header("rpcfg"));
+            rows.extend(rpcfg.into_iter().map(Row::Item));
+        }
+        if !app.is_empty() {
+            rows.push(Row::Header("app"));
+            rows.extend(app.into_iter().map(Row::Item));
+        }
+        rows
+    }
+
+    /// Returns the combined index of the selected item, if any item is visible.
+    fn selected_item(&self, config: &Config) -> Option<usize> {
+        self.visible_items(config).get(self.selected).copied()
+    }
+
+    /// Applies a key press to the state and the configuration.
+    pub fn handle_key(&mut self, config: &mut Config, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            // Like q, but a second Ctrl-C at the prompt discards the changes
+            if self.mode == Mode::ConfirmQuit {
+                self.outcome = Some(Outcome::Quit);
+            } else {
+                self.request_quit(config);
+            }
+            return;
+        }
+        self.status = None;
+        match self.mode.clone() {
+            Mode::Normal => self.handle_normal(config, key.code),
+            Mode::Editing(buffer) => self.handle_editing(config, key.code, buffer),
+            Mode::Searching(buffer) => self.handle_searching(config, key.code, buffer),
+            Mode::ConfirmQuit => self.handle_confirm_quit(key.code),
+        }
+    }
+
+    fn handle_normal(&mut self, config: &mut Config, code: KeyCode) {
+        let count = self.visible_items(config).len();
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = count.saturating_sub(1),
+            KeyCode::Enter => {
+                if let Some(index) = self.selected_item(config) {
+                    let value = nth_item(config, index).value.clone();
+                    self.error = None;
+                    self.mode = Mode::Editing(value);
+                }
+            }
+            KeyCode::Char('/') => self.mode = Mode::Searching(self.filter.clone()),
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.selected = 0;
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => self.outcome = Some(Outcome::Save),
+            KeyCode::Char('q') | KeyCode::Char('Q') => self.request_quit(config),
+            _ => {}
+        }
+    }
+
+    /// Quits, asking first if there are unsaved changes.
+    fn request_quit(&mut self, config: &Config) {
+        if config.is_dirty() {
+            self.mode = Mode::ConfirmQuit;
+        } else {
+            self.outcome = Some(Outcome::Quit);
+        }
+    }
+
+    fn handle_editing(&mut self, config: &mut Config, code: KeyCode, mut buffer: String) {
+        let Some(index) = self.selected_item(config) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.error = None;
+                self.mode = Mode::Normal;
+                return;
+            }
+            KeyCode::Enter => {
+                let item = nth_item(config, index);
+                match item.validate_value(&buffer) {
+                    Ok(()) => {
+                        item.value = buffer;
+                        item.source = ValueSource::Collected;
+                        debug!("Updated item: {:?}", item);
+                        self.status = Some(format!("Updated {}", item.key));
+                        self.error = None;
+                        self.mode = Mode::Normal;
+                    }
+                    Err(message) => {
+                        self.error = Some(message);
+                        self.mode = Mode::Editing(buffer);
+                    }
+                }
+                return;
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => buffer.push(c),
+            _ => {}
+        }
+        self.error = None;
+        self.mode = Mode::Editing(buffer);
+    }
+
+    fn handle_searching(&mut self, config: &Config, code: KeyCode, mut buffer: String) {
+        match code {
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Enter => self.mode = Mode::Normal,
+            KeyCode::Backspace => {
+                buffer.pop();
+                self.filter = buffer.clone();
+                self.mode = Mode::Searching(buffer);
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+                self.filter = buffer.clone();
+                self.mode = Mode::Searching(buffer);
+            }
+            _ => {}
+        }
+        self.selected = self
+            .selected
+            .min(self.visible_items(config).len().saturating_sub(1));
+    }
+
+    fn handle_confirm_quit(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('s') | KeyCode::Char('S') => self.outcome = Some(Outcome::Save),
+            KeyCode::Char('d') | KeyCode::Char('D') => self.outcome = Some(Outcome::Quit),
+            KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+
+    /// Produces the screen as lines of text, fitted to the given height.
+    pub fn lines(&self, config: &Config, height: usize) -> Vec<(LineKind, String)> {
+        let key_width = config
+            .rpcfg
+            .iter()
+            .chain(config.app.iter())
+            .map(|item| item.key.len())
+            .max()
+            .unwrap_or(0);
+        let selected = self.selected_item(config);
+
+        let mut table = Vec::new();
+        let mut selected_line = 0;
+        for row in self.rows(config) {
+            match row {
+                Row::Header(name) => table.push((LineKind::Header, format!("[{}]", name))),
+                Row::Item(index) => {
+                    let item = nth_item_ref(config, index);
+                    let is_selected = Some(index) == selected;
+                    let value = match (&self.mode, is_selected) {
+                        (Mode::Editing(buffer), true) => format!("{}_", item.masked(buffer)),
+                        _ => item.masked(&item.value),
+                    };
+                    if is_selected {
+                        selected_line = table.len();
+                    }
+                    let kind = if is_selected { LineKind::Selected } else { LineKind::Row };
+                    table.push((
+                        kind,
+                        format!(
+                            "  {:<width$}  {}  ({})",
+                            item.key,
+                            value,
+                            item.description,
+                            width = key_width
+                        ),
+                    ));
+                    if is_selected {
+                        if let Some(error) = &self.error {
+                            table.push((LineKind::Error, format!("    ! {}", error)));
+                        }
+                    }
+                }
+            }
+        }
+        if table.is_empty() {
+            table.push((LineKind::Row, format!("  No settings match '{}'", self.filter)));
+        }
+
+        // Title, blank line, and two footer lines take four rows
+        let room = height.saturating_sub(4).max(1);
+        let offset = if selected_line >= room { selected_line + 1 - room } else { 0 };
+
+        let mut lines = vec![(LineKind::Header, self.title(config)), (LineKind::Row, String::new())];
+        lines.extend(table.into_iter().skip(offset).take(room));
+        lines.push((LineKind::Footer, self.status_line()));
+        lines.push((LineKind::Footer, self.help_line()));
+        lines
+    }
+
+    fn title(&self, config: &Config) -> String {
+        let dirty = if config.is_dirty() { " [modified]" } else { "" };
+        let test = if config.is_test { " (Test mode)" } else { "" };
+        format!("rpcfg collect: {}{}{}", config.input_file, dirty, test)
+    }
+
+    fn status_line(&self) -> String {
+        match &self.mode {
+            Mode::Searching(buffer) => format!("/{}", buffer),
+            Mode::ConfirmQuit => "Unsaved changes: (s)ave, (d)iscard, (c)ancel".to_string(),
+            _ if !self.filter.is_empty() => format!("Filter: {}", self.filter),
+            _ => self.status.clone().unwrap_or_default(),
+        }
+    }
+
+    fn help_line(&self) -> String {
+        match &self.mode {
+            Mode::Editing(_) => "Enter accept  Esc cancel".to_string(),
+            Mode::Searching(_) => "Type to filter by key or description  Enter done  Esc clear".to_string(),
+            _ => HELP.to_string(),
+        }
+    }
+}
+
+fn nth_item(config: &mut Config, index: usize) -> &mut crate::models::ConfigItem {
+    config
+        .rpcfg
+        .iter_mut()
+        .chain(config.app.iter_mut())
+        .nth(index)
+        .expect("visible item index is in range")
+}
+
+fn nth_item_ref(config: &Config, index: usize) -> &crate::models::ConfigItem {
+    config
+        .rpcfg
+        .iter()
+        .chain(config.app.iter())
+        .nth(index)
+        .expect("visible item index is in range")
+}
+
+/// Runs a full-screen collect session on the terminal.
+///
+/// The terminal is switched to raw mode on the alternate screen for the duration of the
+/// session and restored afterwards, even if drawing fails.
+///
+/// # Arguments
+///
+/// * `config` - A mutable reference to the Config object to be edited.
+///
+/// # Returns
+///
+/// Returns the Outcome chosen by the user.
+///
+/// # Errors
+///
+/// This function will return an error if the terminal cannot be configured or read.
+pub fn run(config: &mut Config) -> Result<Outcome> {
+    let mut stdout = std::io::stdout();
+    let _guard = TerminalGuard::enter(&mut stdout)?;
+    event_loop(config, &mut stdout)
+}
+
+/// Keeps the terminal in raw mode on the alternate screen, restoring it when dropped.
+///
+/// Dropping restores the terminal on every way out of `run`: a returned outcome, an error
+/// (including one from entering the alternate screen) or a panic.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter<W: Write>(out: &mut W) -> Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Err(e) = execute!(std::io::stdout(), Show, LeaveAlternateScreen) {
+            debug!("Failed to leave the alternate screen: {}", e);
+        }
+        if let Err(e) = terminal::disable_raw_mode() {
+            debug!("Failed to disable raw mode: {}", e);
+        }
+    }
+}
+
+fn event_loop<W: Write>(config: &mut Config, out: &mut W) -> Result<Outcome> {
+    let mut state = TuiState::new();
+    loop {
+        let (_, height) = terminal::size()?;
+        draw(&state.lines(config, height as usize), out)?;
+        if let Event::Key(key) = event::read()? {
+            state.handle_key(config, key);
+        }
+        if let Some(outcome) = state.outcome() {
+            return Ok(outcome);
+        }
+    }
+}
+
+fn draw<W: Write>(lines: &[(LineKind, String)], out: &mut W) -> Result<()> {
+    queue!(out, Clear(ClearType::All))?;
+    for (row, (kind, text)) in lines.iter().enumerate() {
+        queue!(out, MoveTo(0, row as u16))?;
+        match kind {
+            LineKind::Header => queue!(out, SetAttribute(Attribute::Bold), Print(text))?,
+            LineKind::Selected => queue!(out, SetAttribute(Attribute::Reverse), Print(text))?,
+            LineKind::Error => queue!(out, SetForegroundColor(Color::Red), Print(text))?,
+            LineKind::Footer => queue!(out, SetAttribute(Attribute::Dim), Print(text))?,
+            LineKind::Row => queue!(out, Print(text))?,
+        }
+        queue!(out, SetAttribute(Attribute::Reset), SetForegroundColor(Color::Reset))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{safe_test, test_utils::create_test_config};
+    use uuid::Uuid;
+
+    fn press(state: &mut TuiState, config: &mut Config, codes: &[KeyCode]) {
+        for code in codes {
+            state.handle_key(config, KeyEvent::new(*code, KeyModifiers::NONE));
+        }
+    }
+
+    fn type_text(state: &mut TuiState, config: &mut Config, text: &str) {
+        for c in text.chars() {
+            press(state, config, &[KeyCode::Char(c)]);
+        }
+    }
+
+    safe_test!(test_tui_edit_search_and_validate, {
+        let test_id = Uuid::new_v4().to_string();
+        let mut config = create_test_config(&test_id);
+        config.mark_clean();
+        let mut state = TuiState::new();
+
+        // Filter down to the second app item and edit it
+        press(&mut state, &mut config, &[KeyCode::Char('/')]);
+        type_text(&mut state, &mut config, "test item 2");
+        press(&mut state, &mut config, &[KeyCode::Enter, KeyCode::Enter]);
+        type_text(&mut state, &mut config, "edited");
+        press(&mut state, &mut config, &[KeyCode::Enter]);
+        assert_eq!(config.app[1].value, "edited");
+
+        let screen = state.lines(&config, 40);
+        assert!(screen.iter().any(|(_, text)| text == "[app]"));
+        assert!(!screen.iter().any(|(_, text)| text == "[rpcfg]"));
+
+        // An invalid storage type is rejected inline and the edit stays open
+        press(&mut state, &mut config, &[KeyCode::Esc, KeyCode::Home, KeyCode::Enter]);
+        for _ in 0.."local".len() {
+            press(&mut state, &mut config, &[KeyCode::Backspace]);
+        }
+        type_text(&mut state, &mut config, "ftp");
+        press(&mut state, &mut config, &[KeyCode::Enter]);
+        assert_eq!(config.rpcfg[0].value, "local");
+        let screen = state.lines(&config, 40);
+        assert!(screen
+            .iter()
+            .any(|(kind, text)| *kind == LineKind::Error && text.contains("not a storage type")));
+
+        // Quitting with changes asks first
+        press(&mut state, &mut config, &[KeyCode::Esc, KeyCode::Char('q')]);
+        assert_eq!(state.outcome(), None);
+        press(&mut state, &mut config, &[KeyCode::Char('s')]);
+        assert_eq!(state.outcome(), Some(Outcome::Save));
+
+        // Ctrl-C asks too, and a second one discards the changes
+        let mut state = TuiState::new();
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        state.handle_key(&mut config, ctrl_c);
+        assert_eq!(state.outcome(), None);
+        state.handle_key(&mut config, ctrl_c);
+        assert_eq!(state.outcome(), Some(Outcome::Quit));
+
+        Ok(())
+    });
+}
@@ -0,0 +1,254 @@
+use crate::models::{mask_value, Config, ConfigItem};
+
+/// A single change made during a collect session.
+///
+/// Positions in `Added`, `Deleted` and `Moved` are indexes into `config.app`; `Edited` uses
+/// the index across the rpcfg and app items.
+#[derive(Debug, Clone)]
+pub enum Change {
+    Value {
+        key: String,
+        before: String,
+        after: String,
+        secret: bool,
+    },
+    Added {
+        position: usize,
+        item: ConfigItem,
+    },
+    Deleted {
+        position: usize,
+        item: ConfigItem,
+    },
+    Edited {
+        index: usize,
+        before: ConfigItem,
+        after: ConfigItem,
+    },
+    Moved {
+        key: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl Change {
+    /// Returns true if the change affects the settings themselves rather than just a value.
+    pub fn changes_schema(&self) -> bool {
+        !matches!(self, Change::Value { .. })
+    }
+
+    /// Describes the change for the session history, with secret values masked.
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Value {
+                key,
+                before,
+                after,
+                secret,
+            } => format!(
+                "set {}: '{}' -> '{}'",
+                key,
+                mask_value(before, *secret),
+                mask_value(after, *secret)
+            ),
+            Change::Added { item, .. } => {
+                format!("add {} = '{}'", item.key, item.masked(&item.value))
+            }
+            Change::Deleted { item, .. } => {
+                format!("delete {} (was '{}')", item.key, item.masked(&item.value))
+            }
+            Change::Edited { before, after, .. } => {
+                let fields = edited_fields(before, after);
+                if fields.is_empty() {
+                    format!("edit {} (no changes)", after.key)
+                } else {
+                    format!("edit {}: {}", after.key, fields.join(", "))
+                }
+            }
+            Change::Moved { key, from, to } => {
+                format!("move {} from app position {} to {}", key, from + 1, to + 1)
+            }
+        }
+    }
+
+    /// Applies the change to the configuration, or reverses it if `undo` is true.
+    fn apply(&self, config: &mut Config, undo: bool) {
+        match self {
+            Change::Value {
+                key, before, after, ..
+            } => {
+                let value = if undo { before } else { after };
+                for item in config.get_settings_mut(key) {
+                    item.value = value.clone();
+                }
+            }
+            Change::Added { position, item } | Change::Deleted { position, item } => {
+                let insert = matches!(self, Change::Added { .. }) != undo;
+                if insert {
+                    config.app.insert((*position).min(config.app.len()), item.clone());
+                } else if *position < config.app.len() {
+                    config.app.remove(*position);
+                }
+            }
+            Change::Edited {
+                index,
+                before,
+                after,
+            } => {
+                let replacement = if undo { before } else { after };
+                if let Some(item) = config
+                    .rpcfg
+                    .iter_mut()
+                    .chain(config.app.iter_mut())
+                    .nth(*index)
+                {
+                    *item = replacement.clone();
+                }
+            }
+            Change::Moved { from, to, .. } => {
+                let (from, to) = if undo { (*to, *from) } else { (*from, *to) };
+                if from < config.app.len() && to < config.app.len() {
+                    let item = config.app.remove(from);
+                    config.app.insert(to, item);
+                }
+            }
+        }
+    }
+}
+
+/// Lists the fields an edit changed as `field "before" -> "after"`, masking the default of a
+/// secret item.
+fn edited_fields(before: &ConfigItem, after: &ConfigItem) -> Vec<String> {
+    let secret = before.secret || after.secret;
+    let text = [
+        ("key", &before.key, &after.key, false),
+        ("description", &before.description, &after.description, false),
+        ("default", &before.default, &after.default, secret),
+        (
+            "temp_environment_variable_name",
+            &before.temp_environment_variable_name,
+            &after.temp_environment_variable_name,
+            false,
+        ),
+        ("shellscript", &before.shellscript, &after.shellscript, false),
+    ];
+    let mut fields: Vec<String> = text
+        .iter()
+        .filter(|(_, before, after, _)| before != after)
+        .map(|(name, before, after, mask)| {
+            format!("{} {:?} -> {:?}", name, mask_value(before, *mask), mask_value(after, *mask))
+        })
+        .collect();
+    if before.required_as_env != after.required_as_env {
+        fields.push(format!(
+            "required_as_env {} -> {}",
+            before.required_as_env, after.required_as_env
+        ));
+    }
+    if before.secret != after.secret {
+        fields.push(format!("secret {} -> {}", before.secret, after.secret));
+    }
+    if before.item_type != after.item_type {
+        fields.push(format!("type {} -> {}", before.item_type, after.item_type));
+    }
+    fields
+}
+
+/// Undo and redo stacks for a collect session, plus a log of everything that happened.
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    done: Vec<Change>,
+    undone: Vec<Change>,
+    history: Vec<String>,
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a change that has just been made. Any undone changes can no longer be redone.
+    pub fn record(&mut self, change: Change) {
+        self.history.push(change.describe());
+        self.done.push(change);
+        self.undone.clear();
+    }
+
+    /// Reverses the most recent change.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Change>` - The change that was undone, or None if there was nothing to undo
+    pub fn undo(&mut self, config: &mut Config) -> Option<&Change> {
+        let change = self.done.pop()?;
+        change.apply(config, true);
+        self.history.push(format!("undo {}", change.describe()));
+        self.undone.push(change);
+        self.undone.last()
+    }
+
+    /// Re-applies the most recently undone change.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Change>` - The change that was redone, or None if there was nothing to redo
+    pub fn redo(&mut self, config: &mut Config) -> Option<&Change> {
+        let change = self.undone.pop()?;
+        change.apply(config, false);
+        self.history.push(format!("redo {}", change.describe()));
+        self.done.push(change);
+        self.done.last()
+    }
+
+    /// Returns every change, undo and redo in the session, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_test;
+
+    safe_test!(test_describe_edit, {
+        let before = ConfigItem {
+            key: "api_url".to_string(),
+            description: "x".to_string(),
+            default: "old".to_string(),
+            ..Default::default()
+        };
+        let after = ConfigItem {
+            description: "y".to_string(),
+            required_as_env: true,
+            ..before.clone()
+        };
+        let change = Change::Edited {
+            index: 0,
+            before: before.clone(),
+            after,
+        };
+        assert_eq!(
+            change.describe(),
+            "edit api_url: description \"x\" -> \"y\", required_as_env false -> true"
+        );
+
+        // The default of a secret item is masked
+        let secret = ConfigItem {
+            secret: true,
+            ..before.clone()
+        };
+        let change = Change::Edited {
+            index: 0,
+            before: secret.clone(),
+            after: ConfigItem {
+                default: "new".to_string(),
+                ..secret
+            },
+        };
+        assert_eq!(change.describe(), "edit api_url: default \"********\" -> \"********\"");
+
+        Ok(())
+    });
+}
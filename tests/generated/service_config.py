@@ -0,0 +1,115 @@
+# Generated by rpcfg from service_config.json. Do not edit; run `rpcfg codegen --lang python` to update.
+
+from __future__ import annotations
+
+import math
+import os
+import re
+from dataclasses import dataclass
+from typing import Mapping, Optional, Sequence
+
+
+@dataclass(frozen=True, repr=False)
+class ServiceConfig:
+    """The settings of the shop/service_config configuration."""
+
+    api_url: Optional[str]
+    """Base URL of the "orders" API"""
+
+    workers: Optional[int]
+    """Number of worker threads"""
+
+    sample_rate: Optional[float]
+    """Share of requests traced"""
+
+    debug: Optional[bool]
+    """Log request bodies
+    Only for development
+    """
+
+    db_password: str
+    """Password of the orders database
+
+    Secret: do not log this value.
+    """
+
+    type_: str
+    """Kind of service"""
+
+    @classmethod
+    def from_env(cls, env: Optional[Mapping[str, str]] = None) -> ServiceConfig:
+        """Reads the settings from the environment: each item's `temp_environment_variable_name`,
+        then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.
+
+        Raises ValueError naming the setting when a value does not fit its type.
+        """
+        env = os.environ if env is None else env
+        return cls(
+            api_url=_parse_url("apiUrl", _read_env(env, ("ORDERS_API_URL", "RPCFG_APIURL", "APIURL"))),
+            workers=_parse_integer("workers", _read_env(env, ("RPCFG_WORKERS", "WORKERS"))),
+            sample_rate=_parse_number("sample_rate", _read_env(env, ("RPCFG_SAMPLE_RATE", "SAMPLE_RATE"))),
+            debug=_parse_boolean("debug", _read_env(env, ("RPCFG_DEBUG", "DEBUG"))),
+            db_password=_parse_string("db_password", _read_env(env, ("RPCFG_DB_PASSWORD", "DB_PASSWORD"))),
+            type_=_parse_string("type", _read_env(env, ("RPCFG_TYPE", "TYPE"))),
+        )
+
+    def __repr__(self) -> str:
+        """Shows the settings with *** in place of secret values."""
+        return (
+            "ServiceConfig("
+            f"api_url={self.api_url!r}, "
+            f"workers={self.workers!r}, "
+            f"sample_rate={self.sample_rate!r}, "
+            f"debug={self.debug!r}, "
+            "db_password='***', "
+            f"type_={self.type_!r})"
+        )
+
+
+def _read_env(env: Mapping[str, str], names: Sequence[str]) -> str:
+    for name in names:
+        value = env.get(name)
+        if value is not None:
+            return value
+    return ""
+
+
+def _parse_string(_key: str, value: str) -> str:
+    return value
+
+
+def _parse_integer(key: str, value: str) -> Optional[int]:
+    if value == "":
+        return None
+    if not re.fullmatch(r"[+-]?[0-9]+", value):
+        raise ValueError(f"{key}: '{value}' is not a valid integer")
+    return int(value)
+
+
+def _parse_number(key: str, value: str) -> Optional[float]:
+    if value == "":
+        return None
+    try:
+        number = float(value)
+        if value.strip() != value or "_" in value or not math.isfinite(number):
+            raise ValueError
+        return number
+    except ValueError:
+        raise ValueError(f"{key}: '{value}' is not a valid number") from None
+
+
+def _parse_boolean(key: str, value: str) -> Optional[bool]:
+    if value == "":
+        return None
+    if value not in ("true", "false"):
+        raise ValueError(f"{key}: '{value}' is not a valid boolean")
+    return value == "true"
+
+
+def _parse_url(key: str, value: str) -> Optional[str]:
+    if value == "":
+        return None
+    url = value.strip()
+    if "://" not in url or re.search(r"\s", url):
+        raise ValueError(f"{key}: '{value}' is not a valid url")
+    return url
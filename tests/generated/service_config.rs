@@ -0,0 +1,153 @@
+// Generated by rpcfg from service_config.json. Do not edit; run `rpcfg codegen --lang rust` to update.
+
+/// The settings of the shop/service_config configuration.
+#[derive(Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct ServiceConfig {
+    /// Base URL of the "orders" API
+    pub api_url: Option<String>,
+    /// Number of worker threads
+    pub workers: Option<i64>,
+    /// Share of requests traced
+    pub sample_rate: Option<f64>,
+    /// Log request bodies
+    /// Only for development
+    pub debug: Option<bool>,
+    /// Password of the orders database
+    ///
+    /// Secret: do not log this value.
+    pub db_password: String,
+    /// Kind of service
+    pub type_: String,
+}
+
+impl std::fmt::Debug for ServiceConfig {
+    /// Formats the settings with `***` in place of secret values.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceConfig")
+            .field("api_url", &self.api_url)
+            .field("workers", &self.workers)
+            .field("sample_rate", &self.sample_rate)
+            .field("debug", &self.debug)
+            .field("db_password", &"***")
+            .field("type_", &self.type_)
+            .finish()
+    }
+}
+
+#[allow(dead_code)]
+impl ServiceConfig {
+    /// The project the settings are saved under.
+    pub const PROJECT: &str = "shop";
+    /// The config name the settings are saved under.
+    pub const CONFIG_NAME: &str = "service_config";
+    /// The keys of the settings, in schema order.
+    pub const KEYS: &[&str] = &["apiUrl", "workers", "sample_rate", "debug", "db_password", "type"];
+
+    /// Builds the settings from keys and values, such as a saved output, checking each value's type.
+    ///
+    /// A missing key counts as an empty value, which leaves a setting unset.
+    pub fn from_map(values: &std::collections::HashMap<String, String>) -> Result<Self, String> {
+        let get = |key: &str| values.get(key).map(String::as_str).unwrap_or_default();
+        Ok(Self {
+            api_url: Self::parse_url("apiUrl", get("apiUrl"))?,
+            workers: Self::parse_integer("workers", get("workers"))?,
+            sample_rate: Self::parse_number("sample_rate", get("sample_rate"))?,
+            debug: Self::parse_boolean("debug", get("debug"))?,
+            db_password: Self::parse_string("db_password", get("db_password"))?,
+            type_: Self::parse_string("type", get("type"))?,
+        })
+    }
+
+    /// Reads the settings from the environment: the item's `temp_environment_variable_name`,
+    /// then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.
+    pub fn from_env() -> Result<Self, String> {
+        let names: &[(&str, &[&str])] = &[
+            ("apiUrl", &["ORDERS_API_URL", "RPCFG_APIURL", "APIURL"]),
+            ("workers", &["RPCFG_WORKERS", "WORKERS"]),
+            ("sample_rate", &["RPCFG_SAMPLE_RATE", "SAMPLE_RATE"]),
+            ("debug", &["RPCFG_DEBUG", "DEBUG"]),
+            ("db_password", &["RPCFG_DB_PASSWORD", "DB_PASSWORD"]),
+            ("type", &["RPCFG_TYPE", "TYPE"]),
+        ];
+        let values = names
+            .iter()
+            .filter_map(|(key, vars)| {
+                vars.iter()
+                    .find_map(|var| std::env::var(var).ok())
+                    .map(|value| (key.to_string(), value))
+            })
+            .collect();
+        Self::from_map(&values)
+    }
+
+    /// Reads the settings `rpcfg collect` saved for an environment.
+    pub fn load(environment: &str) -> Result<Self, String> {
+        let file = format!("{}-{}.json", Self::CONFIG_NAME, environment);
+        Self::load_file(&Self::storage_root()?.join(Self::PROJECT).join(file))
+    }
+
+    /// Reads the settings from a saved output file.
+    pub fn load_file(path: &std::path::Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let values = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        Self::from_map(&values)
+    }
+
+    /// The rpcfg storage root: `RPCFG_HOME`, `~/.rpcfg` if it exists, then the XDG data directory.
+    fn storage_root() -> Result<std::path::PathBuf, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        if let Some(root) = var("RPCFG_HOME") {
+            return Ok(root.into());
+        }
+        let home = var("HOME").map(std::path::PathBuf::from);
+        if let Some(legacy) = home.as_ref().map(|home| home.join(".rpcfg")).filter(|dir| dir.is_dir()) {
+            return Ok(legacy);
+        }
+        if let Some(data_home) = var("XDG_DATA_HOME") {
+            return Ok(std::path::PathBuf::from(data_home).join("rpcfg"));
+        }
+        home.map(|home| home.join(".local").join("share").join("rpcfg"))
+            .ok_or_else(|| "Cannot find the rpcfg storage root: set RPCFG_HOME or HOME".to_string())
+    }
+
+    fn parse_string(_key: &str, value: &str) -> Result<String, String> {
+        Ok(value.to_string())
+    }
+
+    fn parse_integer(key: &str, value: &str) -> Result<Option<i64>, String> {
+        Self::parse_with(key, value, "integer", |value| value.parse().ok())
+    }
+
+    fn parse_number(key: &str, value: &str) -> Result<Option<f64>, String> {
+        Self::parse_with(key, value, "number", |value| {
+            value.parse().ok().filter(|number: &f64| number.is_finite())
+        })
+    }
+
+    fn parse_boolean(key: &str, value: &str) -> Result<Option<bool>, String> {
+        Self::parse_with(key, value, "boolean", |value| match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        })
+    }
+
+    fn parse_url(key: &str, value: &str) -> Result<Option<String>, String> {
+        Self::parse_with(key, value, "url", |value| {
+            let value = value.trim();
+            (value.contains("://") && !value.contains(char::is_whitespace)).then(|| value.to_string())
+        })
+    }
+
+    fn parse_with<T>(key: &str, value: &str, type_name: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>, String> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        parse(value)
+            .map(Some)
+            .ok_or_else(|| format!("{}: '{}' is not a valid {}", key, value, type_name))
+    }
+}
@@ -0,0 +1,112 @@
+// Generated by rpcfg from service_config.json. Do not edit; run `rpcfg codegen --lang typescript` to update.
+
+/** The settings of the shop/service_config configuration. */
+export interface ServiceConfig {
+  /** Base URL of the "orders" API */
+  apiUrl: string | undefined;
+  /** Number of worker threads */
+  workers: number | undefined;
+  /** Share of requests traced */
+  sampleRate: number | undefined;
+  /**
+   * Log request bodies
+   * Only for development
+   */
+  debug: boolean | undefined;
+  /**
+   * Password of the orders database
+   *
+   * Secret: do not log this value.
+   */
+  dbPassword: string;
+  /** Kind of service */
+  type_: string;
+}
+
+/**
+ * Reads the settings from the environment: each item's `temp_environment_variable_name`,
+ * then `RPCFG_<KEY>`, then the uppercased key used in the `.env` rpcfg writes.
+ *
+ * Throws an Error naming the setting when a value does not fit its type.
+ */
+export function loadServiceConfig(env: Record<string, string | undefined> = process.env): ServiceConfig {
+  return maskSecrets({
+    apiUrl: parseUrl("apiUrl", readEnv(env, ["ORDERS_API_URL", "RPCFG_APIURL", "APIURL"])),
+    workers: parseInteger("workers", readEnv(env, ["RPCFG_WORKERS", "WORKERS"])),
+    sampleRate: parseNumber("sample_rate", readEnv(env, ["RPCFG_SAMPLE_RATE", "SAMPLE_RATE"])),
+    debug: parseBoolean("debug", readEnv(env, ["RPCFG_DEBUG", "DEBUG"])),
+    dbPassword: parseString("db_password", readEnv(env, ["RPCFG_DB_PASSWORD", "DB_PASSWORD"])),
+    type_: parseString("type", readEnv(env, ["RPCFG_TYPE", "TYPE"])),
+  }, ["dbPassword"]);
+}
+
+function readEnv(env: Record<string, string | undefined>, names: readonly string[]): string {
+  for (const name of names) {
+    const value = env[name];
+    if (value !== undefined) {
+      return value;
+    }
+  }
+  return "";
+}
+
+function maskSecrets<T extends object>(settings: T, secrets: readonly string[]): T {
+  const masked = (): Record<string, unknown> =>
+    Object.fromEntries(
+      Object.entries(settings).map(([key, value]) => [key, secrets.includes(key) ? "***" : value]),
+    );
+  Object.defineProperties(settings, {
+    toJSON: { value: masked },
+    toString: { value: () => JSON.stringify(masked()) },
+    [Symbol.for("nodejs.util.inspect.custom")]: { value: masked },
+  });
+  return settings;
+}
+
+function parseString(_key: string, value: string): string {
+  return value;
+}
+
+function parseInteger(key: string, value: string): number | undefined {
+  if (value === "") {
+    return undefined;
+  }
+  const number = Number(value);
+  // Beyond 2^53 a number cannot hold every integer, so the value would silently change
+  if (!/^[+-]?[0-9]+$/.test(value) || !Number.isSafeInteger(number)) {
+    throw new Error(`${key}: '${value}' is not a valid integer`);
+  }
+  return number;
+}
+
+function parseNumber(key: string, value: string): number | undefined {
+  if (value === "") {
+    return undefined;
+  }
+  const number = Number(value);
+  if (value.trim() !== value || !Number.isFinite(number)) {
+    throw new Error(`${key}: '${value}' is not a valid number`);
+  }
+  return number;
+}
+
+function parseBoolean(key: string, value: string): boolean | undefined {
+  if (value === "") {
+    return undefined;
+  }
+  if (value !== "true" && value !== "false") {
+    throw new Error(`${key}: '${value}' is not a valid boolean`);
+  }
+  return value === "true";
+}
+
+function parseUrl(key: string, value: string): string | undefined {
+  if (value === "") {
+    return undefined;
+  }
+  const url = value.trim();
+  if (!url.includes("://") || /\s/.test(url)) {
+    throw new Error(`${key}: '${value}' is not a valid url`);
+  }
+  return url;
+}
@@ -1 +1,72 @@
+use rpcfg::codegen::{generate_file, CodegenOptions, Lang};
+use std::collections::HashMap;
 
+// The committed output of `rpcfg codegen`; the build fails here when the generator emits invalid code
+mod generated {
+    include!("generated/service_config.rs");
+}
+
+use generated::ServiceConfig;
+
+const SCHEMA: &str = "tests/fixtures/service_config.json";
+
+#[test]
+fn generated_rust_is_up_to_date() {
+    let code = generate_file(SCHEMA, Lang::Rust, &CodegenOptions::default()).unwrap();
+    assert!(
+        code == include_str!("generated/service_config.rs"),
+        "run `cargo run -- codegen -i {} --lang rust -o tests/generated/service_config.rs`",
+        SCHEMA
+    );
+}
+
+// The TypeScript and Python outputs cannot be compiled here, so only drift is checked
+#[test]
+fn generated_typescript_and_python_are_up_to_date() {
+    let typescript = generate_file(SCHEMA, Lang::TypeScript, &CodegenOptions::default()).unwrap();
+    assert!(
+        typescript == include_str!("generated/service_config.ts"),
+        "run `cargo run -- codegen -i {} --lang typescript -o tests/generated/service_config.ts`",
+        SCHEMA
+    );
+    let python = generate_file(SCHEMA, Lang::Python, &CodegenOptions::default()).unwrap();
+    assert!(
+        python == include_str!("generated/service_config.py"),
+        "run `cargo run -- codegen -i {} --lang python -o tests/generated/service_config.py`",
+        SCHEMA
+    );
+}
+
+#[test]
+fn generated_rust_reads_typed_values() {
+    let values: HashMap<String, String> = [
+        ("apiUrl", "https://orders.example.com"),
+        ("workers", "8"),
+        ("sample_rate", "0.5"),
+        ("debug", "true"),
+        ("db_password", "hunter2"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    let config = ServiceConfig::from_map(&values).unwrap();
+    assert_eq!(config.api_url.as_deref(), Some("https://orders.example.com"));
+    assert_eq!(config.workers, Some(8));
+    assert_eq!(config.sample_rate, Some(0.5));
+    assert_eq!(config.debug, Some(true));
+    assert_eq!(config.type_, "");
+    // Secret values are masked in Debug output
+    let debug = format!("{:?}", config);
+    assert!(debug.contains("db_password: \"***\""));
+    assert!(!debug.contains("hunter2"));
+
+    let mut invalid = values.clone();
+    invalid.insert("workers".to_string(), "many".to_string());
+    let err = ServiceConfig::from_map(&invalid).unwrap_err();
+    assert_eq!(err, "workers: 'many' is not a valid integer");
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("service_config-development.json");
+    std::fs::write(&path, serde_json::to_string(&values).unwrap()).unwrap();
+    assert_eq!(ServiceConfig::load_file(&path).unwrap(), config);
+}